@@ -1,8 +1,11 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
 
 use serde::Deserialize;
 
-use super::utils::{Os, OsName};
+use super::utils::{self, Arch, Os, OsName, Platform};
 
 #[derive(Deserialize, Debug, Clone, Copy)]
 #[serde(rename_all = "lowercase")]
@@ -17,17 +20,58 @@ pub struct Rule {
     pub os: Option<Os>,
 }
 
+/// The launcher features a [`Rule`] can depend on, such as `is_demo_user` or `has_custom_resolution`
+/// a feature that isn't enabled is considered disabled
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Features(HashSet<String>);
+
+impl Features {
+    pub const IS_DEMO_USER: &'static str = "is_demo_user";
+    pub const HAS_CUSTOM_RESOLUTION: &'static str = "has_custom_resolution";
+    pub const HAS_QUICK_PLAYS_SUPPORT: &'static str = "has_quick_plays_support";
+    pub const IS_QUICK_PLAY_SINGLEPLAYER: &'static str = "is_quick_play_singleplayer";
+    pub const IS_QUICK_PLAY_MULTIPLAYER: &'static str = "is_quick_play_multiplayer";
+    pub const IS_QUICK_PLAY_REALMS: &'static str = "is_quick_play_realms";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn enable(&mut self, feature: &str) {
+        self.0.insert(feature.to_string());
+    }
+
+    /// [`Features::enable`] but returns `self` so it can be chained
+    pub fn with(mut self, feature: &str) -> Self {
+        self.enable(feature);
+        self
+    }
+
+    pub fn is_enabled(&self, feature: &str) -> bool {
+        self.0.contains(feature)
+    }
+}
+
 impl Rule {
-    /// Returns true if the current platform allows the given [`Rule`]
+    /// Returns true if the current platform and the given `features` match the given [`Rule`]
     /// use [`Rule::is_allowed`] to check if a rule is allowed on a given platform this only checks if the rule matches the current platform isn't impacted by the action
-    fn matches(&self) -> bool {
-        // TODO: match features
-        (self.os.is_none() || self.os.as_ref().is_some_and(|os| os.matches()))
-            && self.features.is_none()
+    fn matches(&self, features: &Features, platform: Platform) -> bool {
+        let os_matches = self.os.as_ref().is_none_or(|os| os.matches_on(platform));
+        let features_match = self.features.as_ref().is_none_or(|required| {
+            required
+                .iter()
+                .all(|(feature, enabled)| features.is_enabled(feature) == *enabled)
+        });
+        os_matches && features_match
+    }
+
+    pub fn is_allowed(&self, features: &Features) -> bool {
+        self.is_allowed_on(features, Platform::CURRENT)
     }
 
-    pub fn is_allowed(&self) -> bool {
-        let is_matched = self.matches();
+    /// [`Rule::is_allowed`] on the given `platform` instead of the current one
+    pub fn is_allowed_on(&self, features: &Features, platform: Platform) -> bool {
+        let is_matched = self.matches(features, platform);
         match self.action {
             RuleAction::Allow => is_matched,
             RuleAction::Disallow => !is_matched,
@@ -66,11 +110,12 @@ pub enum Argument {
 }
 
 impl Argument {
-    fn into_raw(self) -> Vec<String> {
+    /// returns the raw arguments if they are allowed by their rules with the given `features`
+    pub fn into_raw(self, features: &Features) -> Vec<String> {
         match self {
             Argument::Arg(arg) => vec![arg],
             Argument::Rule { rules, value } => {
-                if rules.iter().all(Rule::is_allowed) {
+                if rules.iter().all(|rule| rule.is_allowed(features)) {
                     match value {
                         ArgValue::Value(value) => vec![value],
                         ArgValue::Values(values) => values,
@@ -93,13 +138,35 @@ pub enum Arguments {
 }
 
 impl Arguments {
+    /// returns true if any of the game arguments depends on the given launcher `feature`
+    /// legacy `minecraftArguments` don't support any feature
+    pub fn supports_feature(&self, feature: &str) -> bool {
+        let Arguments::Args { game, .. } = self else {
+            return false;
+        };
+        game.iter().any(|arg| match arg {
+            Argument::Arg(_) => false,
+            Argument::Rule { rules, .. } => rules.iter().any(|rule| {
+                rule.features
+                    .as_ref()
+                    .is_some_and(|features| features.contains_key(feature))
+            }),
+        })
+    }
+
     /// maps `Arguments` to (JVM Args, Game Args)
-    /// only maps arguments that are allowed by their rules
-    pub fn into_raw(self) -> (Vec<String>, Vec<String>) {
+    /// only maps arguments that are allowed by their rules with the given `features`
+    pub fn into_raw(self, features: &Features) -> (Vec<String>, Vec<String>) {
         match self {
             Arguments::Args { game, jvm } => {
-                let jvm: Vec<String> = jvm.into_iter().map(Argument::into_raw).flatten().collect();
-                let game = game.into_iter().map(Argument::into_raw).flatten().collect();
+                let jvm: Vec<String> = jvm
+                    .into_iter()
+                    .flat_map(|arg| arg.into_raw(features))
+                    .collect();
+                let game = game
+                    .into_iter()
+                    .flat_map(|arg| arg.into_raw(features))
+                    .collect();
                 (jvm, game)
             }
             Arguments::MinecraftArgs(args) => {
@@ -138,33 +205,100 @@ pub struct Extract {
     pub exclude: Option<Vec<PathBuf>>,
 }
 
-pub type Natives = HashMap<OsName, String>;
+/// maps an OS, optionally followed by an arch such as `linux-arm64`, to the classifier of its natives
+pub type Natives = HashMap<String, String>;
 #[derive(Debug, Deserialize)]
 pub struct Library {
+    /// the maven coordinates of the library, `group:artifact:version[:classifier]`
+    #[serde(default)]
+    pub name: String,
     pub downloads: LibraryDownload,
     pub extract: Option<Extract>,
     pub natives: Option<Natives>,
     pub rules: Option<Vec<Rule>>,
 }
 impl Library {
-    pub fn is_allowed(&self) -> bool {
-        self.rules.is_none()
-            || self
-                .rules
-                .as_ref()
-                .is_some_and(|rules| rules.iter().all(Rule::is_allowed))
+    pub fn is_allowed(&self, features: &Features) -> bool {
+        self.is_allowed_on(features, Platform::CURRENT)
+    }
+
+    /// [`Library::is_allowed`] on the given `platform` instead of the current one
+    /// natives built for another arch than the platform's, such as `natives-macos-arm64` on x86_64, aren't allowed
+    pub fn is_allowed_on(&self, features: &Features, platform: Platform) -> bool {
+        let rules_allow = self.rules.as_ref().is_none_or(|rules| {
+            rules
+                .iter()
+                .all(|rule| rule.is_allowed_on(features, platform))
+        });
+        let arch_matches = self
+            .native_platform()
+            .and_then(|(_, arch)| arch)
+            .is_none_or(|arch| arch == platform.arch);
+        rules_allow && arch_matches
+    }
+
+    /// the classifier of the library, e.g. `natives-linux` for `org.lwjgl:lwjgl:3.3.1:natives-linux`
+    pub fn classifier(&self) -> Option<&str> {
+        self.name.splitn(4, ':').nth(3)
+    }
+
+    /// the platform the library is the natives of, for the natives published as their own library since 1.19
+    fn native_platform(&self) -> Option<(OsName, Option<Arch>)> {
+        let platform = self.classifier()?.strip_prefix("natives-")?;
+        utils::parse_platform(platform)
+    }
+
+    /// whether the library is itself a jar of natives, as done since 1.19 instead of listing [`Library::natives`]
+    pub fn is_native_artifact(&self) -> bool {
+        self.native_platform().is_some()
     }
 
     // TODO: consider this when implementing our own meta format
     /// returns the native library [`Download`] required by the library for the current platform
     pub fn platform_native(&self) -> Option<&Download> {
+        self.native_for(Platform::CURRENT)
+    }
+
+    /// returns the jar of natives to extract for `platform`
+    /// which is either the artifact of a natives library or the classifier listed by [`Library::natives`]
+    pub fn native_for(&self, platform: Platform) -> Option<&Download> {
+        if self.is_native_artifact() {
+            return self.downloads.artifact.as_ref();
+        }
+        self.native_classifier(platform)
+    }
+
+    /// returns the classifier listed by [`Library::natives`] for `platform`
+    /// keys and classifiers naming the platform's arch, such as `linux-arm64`, are preferred over the ones naming only its OS
+    pub fn native_classifier(&self, platform: Platform) -> Option<&Download> {
         let natives = self.natives.as_ref()?;
         let classifiers = self.downloads.classifiers.as_ref()?;
-        let mut results = natives
+
+        let (_, classifier) = natives
             .iter()
-            .filter(|(os, _)| **os == crate::OS)
-            .map(|(_, native)| classifiers.get(native).unwrap());
-        results.next()
+            .filter_map(|(key, classifier)| {
+                let (os, arch) = utils::parse_platform(key)?;
+                let matches = os == platform.os && arch.is_none_or(|arch| arch == platform.arch);
+                matches.then_some((arch.is_some(), classifier))
+            })
+            .max_by_key(|(specific, _)| *specific)?;
+        let classifier = classifier.replace("${arch}", platform.arch.bits());
+
+        platform
+            .arch
+            .suffixes()
+            .iter()
+            .map(|suffix| format!("{classifier}-{suffix}"))
+            .chain([classifier.clone()])
+            .find_map(|classifier| classifiers.get(&classifier))
+    }
+
+    /// returns the jars of the library to download and put on the classpath on `platform`
+    pub fn jars(&self, platform: Platform) -> impl Iterator<Item = &Download> {
+        self.downloads
+            .artifact
+            .iter()
+            .chain(self.native_classifier(platform))
     }
 }
 
@@ -194,11 +328,46 @@ pub struct Object {
 #[derive(Deserialize, Debug)]
 pub struct Index {
     pub objects: HashMap<String, Object>,
+    /// the assets are read by name from `assets/virtual/<id>` instead of by hash, used by versions up to 1.7.2
+    #[serde(default, rename = "virtual")]
+    pub is_virtual: bool,
+    /// the assets are read by name from the `resources` directory of the game directory, used by versions up to 1.5.2
+    #[serde(default)]
+    pub map_to_resources: bool,
 }
 
 impl Client {
     /// returns an iterator of all libraries that are required by the current platform
+    /// libraries don't depend on launcher features so they are evaluated with none enabled
     pub fn libs(&self) -> impl Iterator<Item = &Library> {
-        self.libraries.iter().filter(|l| l.is_allowed())
+        platform_libs(&self.libraries, Platform::CURRENT)
     }
 }
+
+/// returns the libraries of `libraries` that are required by `platform`, see [`Client::libs`]
+/// natives built for the platform's arch replace the generic natives of the same library, e.g. `natives-macos-arm64` replaces `natives-macos`
+pub fn platform_libs(libraries: &[Library], platform: Platform) -> impl Iterator<Item = &Library> {
+    let features = Features::new();
+    let allowed: Vec<_> = libraries
+        .iter()
+        .filter(|l| l.is_allowed_on(&features, platform))
+        .collect();
+
+    let specific: HashSet<(&str, OsName)> = allowed
+        .iter()
+        .filter_map(|l| match l.native_platform()? {
+            (os, Some(_)) => Some((l.name.rsplit_once(':')?.0, os)),
+            (_, None) => None,
+        })
+        .collect();
+
+    allowed
+        .into_iter()
+        .filter(move |l| match l.native_platform() {
+            Some((os, None)) => l
+                .name
+                .rsplit_once(':')
+                .is_none_or(|(coordinates, _)| !specific.contains(&(coordinates, os))),
+            _ => true,
+        })
+}
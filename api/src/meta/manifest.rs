@@ -1,6 +1,8 @@
+use std::fmt::Display;
+
 use serde::Deserialize;
 
-#[derive(Debug, Deserialize, Clone, Copy)]
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum VersionKind {
     Release,
@@ -9,13 +11,41 @@ pub enum VersionKind {
     OldBeta,
 }
 
+impl Display for VersionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            VersionKind::Release => "release",
+            VersionKind::Snapshot => "snapshot",
+            VersionKind::OldAlpha => "old_alpha",
+            VersionKind::OldBeta => "old_beta",
+        };
+        f.write_str(name)
+    }
+}
+
 #[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
 pub struct Version {
     pub id: String,
+    #[serde(rename = "type")]
+    pub kind: VersionKind,
     pub url: String,
+    /// SHA-1 of the version's client.json, only given by the v2 manifest
+    pub sha1: String,
+    /// RFC 3339 timestamp, Mojang always uses the UTC offset so these can be compared as strings
+    pub time: String,
+    /// RFC 3339 timestamp, see [`Version::time`]
+    pub release_time: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Latest {
+    pub release: String,
+    pub snapshot: String,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct VersionManifest {
+    pub latest: Latest,
     pub versions: Vec<Version>,
 }
@@ -1,3 +1,4 @@
 pub mod client;
 pub mod manifest;
+pub mod runtime;
 pub mod utils;
@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use super::{
+    client::Download,
+    utils::{Arch, OsName, Platform},
+};
+
+/// The Java runtimes published by Mojang, by platform then by component such as `java-runtime-gamma`
+pub type RuntimeIndex = HashMap<String, HashMap<String, Vec<Runtime>>>;
+
+#[derive(Debug, Deserialize)]
+pub struct RuntimeVersion {
+    pub name: String,
+}
+
+/// A release of a runtime component for a platform
+#[derive(Debug, Deserialize)]
+pub struct Runtime {
+    /// the [`RuntimeManifest`] listing the files of the runtime
+    pub manifest: Download,
+    pub version: RuntimeVersion,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FileDownloads {
+    pub raw: Download,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum RuntimeFile {
+    File {
+        #[serde(default)]
+        executable: bool,
+        downloads: FileDownloads,
+    },
+    Directory,
+    /// a symlink, `target` is relative to the directory of the link
+    Link {
+        target: String,
+    },
+}
+
+/// The files of a runtime, by their path inside the runtime's directory
+#[derive(Debug, Deserialize)]
+pub struct RuntimeManifest {
+    pub files: HashMap<String, RuntimeFile>,
+}
+
+/// the key of `platform` in the [`RuntimeIndex`]
+pub fn platform_key(platform: Platform) -> &'static str {
+    match (platform.os, platform.arch) {
+        (OsName::Linux, Arch::X86_64) => "linux",
+        (OsName::Linux, Arch::X86) => "linux-i386",
+        // Mojang doesn't publish runtimes for it, but the x86_64 ones wouldn't run anyway
+        (OsName::Linux, Arch::ARM64) => "linux-arm64",
+        (OsName::Osx, Arch::ARM64) => "mac-os-arm64",
+        (OsName::Osx, _) => "mac-os",
+        (OsName::Windows, Arch::X86_64) => "windows-x64",
+        (OsName::Windows, Arch::X86) => "windows-x86",
+        (OsName::Windows, Arch::ARM64) => "windows-arm64",
+    }
+}
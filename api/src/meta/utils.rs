@@ -8,14 +8,84 @@ pub enum OsName {
     Osx,
 }
 
+impl OsName {
+    /// parses the OS part of a natives key or classifier, e.g. `linux` in `natives-linux-arm64`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "linux" => Some(OsName::Linux),
+            "windows" => Some(OsName::Windows),
+            "osx" | "macos" => Some(OsName::Osx),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum Arch {
     X86_64,
     X86,
+    #[serde(rename = "arm64", alias = "aarch64")]
     ARM64,
 }
 
+impl Arch {
+    /// parses the arch suffix of a natives key or classifier, e.g. `arm64` in `natives-linux-arm64`
+    pub fn from_suffix(suffix: &str) -> Option<Self> {
+        match suffix {
+            "x86_64" | "x64" | "amd64" => Some(Arch::X86_64),
+            "x86" => Some(Arch::X86),
+            "arm64" | "aarch64" => Some(Arch::ARM64),
+            _ => None,
+        }
+    }
+
+    /// the value of `${arch}` in natives classifiers such as `natives-windows-${arch}`
+    pub fn bits(self) -> &'static str {
+        match self {
+            Arch::X86 => "32",
+            Arch::X86_64 | Arch::ARM64 => "64",
+        }
+    }
+
+    /// the suffixes of the classifiers built for this arch specifically, such as `natives-linux-arm64`
+    pub fn suffixes(self) -> &'static [&'static str] {
+        match self {
+            Arch::X86_64 => &[],
+            Arch::X86 => &["x86"],
+            Arch::ARM64 => &["arm64", "aarch64"],
+        }
+    }
+}
+
+/// An OS and an arch that libraries and rules can be evaluated for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Platform {
+    pub os: OsName,
+    pub arch: Arch,
+}
+
+impl Platform {
+    /// the platform the launcher is running on
+    pub const CURRENT: Platform = Platform {
+        os: crate::OS,
+        arch: crate::ARCH,
+    };
+
+    pub const fn new(os: OsName, arch: Arch) -> Self {
+        Self { os, arch }
+    }
+}
+
+/// parses a natives key or classifier without its `natives-` prefix, such as `linux` or `macos-arm64`
+/// returns the OS it names and its arch if it names one
+pub fn parse_platform(name: &str) -> Option<(OsName, Option<Arch>)> {
+    match name.split_once('-') {
+        Some((os, arch)) => Some((OsName::from_name(os)?, Some(Arch::from_suffix(arch)?))),
+        None => Some((OsName::from_name(name)?, None)),
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct Os {
     pub name: Option<OsName>,
@@ -25,7 +95,12 @@ pub struct Os {
 impl Os {
     /// Returns true if the current platform matches the given [`Os`]
     pub fn matches(&self) -> bool {
-        (self.name.is_none() || self.name == Some(crate::OS))
-            && (self.arch.is_none() || self.arch == Some(crate::ARCH))
+        self.matches_on(Platform::CURRENT)
+    }
+
+    /// Returns true if `platform` matches the given [`Os`]
+    pub fn matches_on(&self, platform: Platform) -> bool {
+        (self.name.is_none() || self.name == Some(platform.os))
+            && (self.arch.is_none() || self.arch == Some(platform.arch))
     }
 }
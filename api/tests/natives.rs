@@ -0,0 +1,226 @@
+use crab_launcher_api::meta::{
+    client::{platform_libs, Features, Library},
+    utils::{Arch, OsName, Platform},
+};
+use serde::Deserialize;
+
+const LINUX: Platform = Platform::new(OsName::Linux, Arch::X86_64);
+const LINUX_ARM64: Platform = Platform::new(OsName::Linux, Arch::ARM64);
+const MACOS: Platform = Platform::new(OsName::Osx, Arch::X86_64);
+const MACOS_ARM64: Platform = Platform::new(OsName::Osx, Arch::ARM64);
+const WINDOWS: Platform = Platform::new(OsName::Windows, Arch::X86_64);
+const WINDOWS_X86: Platform = Platform::new(OsName::Windows, Arch::X86);
+const WINDOWS_ARM64: Platform = Platform::new(OsName::Windows, Arch::ARM64);
+
+#[derive(Deserialize)]
+struct LibrariesBlock {
+    libraries: Vec<Library>,
+}
+
+fn parse(raw: &str) -> Vec<Library> {
+    serde_json::from_str::<LibrariesBlock>(raw)
+        .expect("failed to parse libraries")
+        .libraries
+}
+
+/// the libraries of the 1.8.9 client.json with natives, natives are classifiers selected by OS
+fn libraries_1_8_9() -> Vec<Library> {
+    parse(include_str!("data/libraries-1.8.9.json"))
+}
+
+/// the `lwjgl` libraries of the 1.16.5 client.json, macOS uses an older version than the other OSes
+fn libraries_1_16_5() -> Vec<Library> {
+    parse(include_str!("data/libraries-1.16.5.json"))
+}
+
+/// the `lwjgl` libraries of the 1.19 client.json, each natives jar is a library of its own
+fn libraries_1_19() -> Vec<Library> {
+    parse(include_str!("data/libraries-1.19.json"))
+}
+
+fn find(libraries: Vec<Library>, name: &str) -> Library {
+    libraries
+        .into_iter()
+        .find(|library| library.name == name)
+        .expect("library not listed")
+}
+
+fn native_path(library: &Library, platform: Platform) -> Option<String> {
+    let native = library.native_for(platform)?;
+    Some(native.sub_path.as_ref()?.display().to_string())
+}
+
+/// the natives to extract for each library required by `platform`
+fn native_paths(libraries: &[Library], platform: Platform) -> Vec<String> {
+    platform_libs(libraries, platform)
+        .filter_map(|library| native_path(library, platform))
+        .collect()
+}
+
+fn names(libraries: &[Library], platform: Platform) -> Vec<&str> {
+    platform_libs(libraries, platform)
+        .map(|library| library.name.as_str())
+        .collect()
+}
+
+#[test]
+fn natives_selected_by_os() {
+    let library = find(
+        libraries_1_8_9(),
+        "org.lwjgl.lwjgl:lwjgl-platform:2.9.4-nightly-20150209",
+    );
+    let path = "org/lwjgl/lwjgl/lwjgl-platform/2.9.4-nightly-20150209/lwjgl-platform-2.9.4-nightly-20150209";
+
+    assert_eq!(
+        native_path(&library, LINUX),
+        Some(format!("{path}-natives-linux.jar"))
+    );
+    assert_eq!(
+        native_path(&library, MACOS),
+        Some(format!("{path}-natives-osx.jar"))
+    );
+    assert_eq!(
+        native_path(&library, WINDOWS),
+        Some(format!("{path}-natives-windows.jar"))
+    );
+    // there are no arm64 natives, so the generic ones are the best match
+    assert_eq!(
+        native_path(&library, LINUX_ARM64),
+        Some(format!("{path}-natives-linux.jar"))
+    );
+    assert_eq!(library.jars(LINUX).count(), 2);
+    assert!(!library.is_native_artifact());
+}
+
+#[test]
+fn arch_is_substituted_in_classifiers() {
+    let libraries = libraries_1_8_9();
+    let platform = "tv/twitch/twitch-platform/6.5/twitch-platform-6.5";
+    let external = "tv/twitch/twitch-external-platform/4.5/twitch-external-platform-4.5";
+
+    assert_eq!(
+        native_paths(&libraries, WINDOWS)[..2],
+        [
+            format!("{platform}-natives-windows-64.jar"),
+            format!("{external}-natives-windows-64.jar"),
+        ]
+    );
+    assert_eq!(
+        native_paths(&libraries, WINDOWS_X86)[..2],
+        [
+            format!("{platform}-natives-windows-32.jar"),
+            format!("{external}-natives-windows-32.jar"),
+        ]
+    );
+
+    let twitch = find(libraries, "tv.twitch:twitch-platform:6.5");
+    assert!(!twitch.is_allowed_on(&Features::new(), LINUX));
+    assert_eq!(native_path(&twitch, LINUX), None);
+}
+
+#[test]
+fn natives_of_each_library_are_extracted() {
+    let libraries = libraries_1_8_9();
+
+    assert_eq!(
+        native_paths(&libraries, LINUX),
+        [
+            "org/lwjgl/lwjgl/lwjgl-platform/2.9.4-nightly-20150209/lwjgl-platform-2.9.4-nightly-20150209-natives-linux.jar",
+            "net/java/jinput/jinput-platform/2.0.5/jinput-platform-2.0.5-natives-linux.jar",
+        ]
+    );
+    assert_eq!(native_paths(&libraries, MACOS).len(), 3);
+    assert_eq!(native_paths(&libraries, WINDOWS).len(), 4);
+}
+
+#[test]
+fn missing_classifier_is_skipped() {
+    let mut library = find(libraries_1_8_9(), "net.java.jinput:jinput-platform:2.0.5");
+    let classifiers = library.downloads.classifiers.as_mut().unwrap();
+    classifiers.remove("natives-linux");
+
+    assert_eq!(native_path(&library, LINUX), None);
+    // only the artifact is left to download
+    assert_eq!(library.jars(LINUX).count(), 1);
+    assert!(native_path(&library, MACOS).is_some());
+}
+
+#[test]
+fn classifiers_named_after_another_os_key() {
+    let libraries = libraries_1_16_5();
+
+    assert_eq!(
+        native_paths(&libraries, MACOS),
+        ["org/lwjgl/lwjgl/3.2.1/lwjgl-3.2.1-natives-macos.jar"]
+    );
+    assert_eq!(
+        native_paths(&libraries, LINUX),
+        ["org/lwjgl/lwjgl/3.2.2/lwjgl-3.2.2-natives-linux.jar"]
+    );
+    assert_eq!(
+        native_paths(&libraries, WINDOWS),
+        ["org/lwjgl/lwjgl/3.2.2/lwjgl-3.2.2-natives-windows.jar"]
+    );
+    assert_eq!(
+        names(&libraries, MACOS),
+        ["org.lwjgl:lwjgl:3.2.1", "org.lwjgl:lwjgl:3.2.1"]
+    );
+}
+
+#[test]
+fn javadoc_and_sources_are_not_downloaded() {
+    let libraries = libraries_1_16_5();
+    let jars: Vec<_> = platform_libs(&libraries, LINUX)
+        .flat_map(|library| library.jars(LINUX))
+        .map(|jar| jar.sub_path.as_ref().unwrap().display().to_string())
+        .collect();
+
+    assert_eq!(
+        jars,
+        [
+            "org/lwjgl/lwjgl/3.2.2/lwjgl-3.2.2.jar",
+            "org/lwjgl/lwjgl/3.2.2/lwjgl-3.2.2.jar",
+            "org/lwjgl/lwjgl/3.2.2/lwjgl-3.2.2-natives-linux.jar",
+        ]
+    );
+}
+
+#[test]
+fn natives_libraries_selected_by_os_and_arch() {
+    let libraries = libraries_1_19();
+    let names = |platform| names(&libraries, platform)[1..].join(" ");
+
+    assert_eq!(names(LINUX), "org.lwjgl:lwjgl:3.3.1:natives-linux");
+    // there are no linux arm64 natives, so the generic ones are the best match
+    assert_eq!(names(LINUX_ARM64), "org.lwjgl:lwjgl:3.3.1:natives-linux");
+    assert_eq!(names(MACOS), "org.lwjgl:lwjgl:3.3.1:natives-macos");
+    assert_eq!(
+        names(MACOS_ARM64),
+        "org.lwjgl:lwjgl:3.3.1:natives-macos-arm64"
+    );
+    assert_eq!(names(WINDOWS), "org.lwjgl:lwjgl:3.3.1:natives-windows");
+    assert_eq!(
+        names(WINDOWS_X86),
+        "org.lwjgl:lwjgl:3.3.1:natives-windows-x86"
+    );
+    assert_eq!(
+        names(WINDOWS_ARM64),
+        "org.lwjgl:lwjgl:3.3.1:natives-windows-arm64"
+    );
+}
+
+#[test]
+fn natives_libraries_are_extracted() {
+    let libraries = libraries_1_19();
+    let natives = &libraries[1];
+
+    assert!(natives.is_native_artifact());
+    assert_eq!(
+        native_path(natives, LINUX).as_deref(),
+        Some("org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1-natives-linux.jar")
+    );
+    // the natives jar is downloaded once, as the library's artifact
+    assert_eq!(natives.jars(LINUX).count(), 1);
+    assert!(!libraries[0].is_native_artifact());
+    assert_eq!(native_path(&libraries[0], LINUX), None);
+}
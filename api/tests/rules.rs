@@ -0,0 +1,144 @@
+use crab_launcher_api::meta::client::{Arguments, Features, Library, Rule};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct ArgumentsBlock {
+    arguments: Arguments,
+}
+
+/// the `arguments` block of the 1.20.1 client.json
+fn arguments() -> Arguments {
+    let raw = include_str!("data/arguments-1.20.1.json");
+    serde_json::from_str::<ArgumentsBlock>(raw)
+        .expect("failed to parse 1.20.1 arguments")
+        .arguments
+}
+
+fn game_args(features: &Features) -> Vec<String> {
+    arguments().into_raw(features).1
+}
+
+fn contains_sequence(args: &[String], sequence: &[&str]) -> bool {
+    args.windows(sequence.len())
+        .any(|window| window == sequence)
+}
+
+#[test]
+fn no_features_skips_feature_arguments() {
+    let args = game_args(&Features::new());
+    assert_eq!(args.len(), 22);
+    assert!(!args.iter().any(|arg| arg == "--demo"));
+    assert!(!args.iter().any(|arg| arg.starts_with("--quickPlay")));
+    assert!(!args.iter().any(|arg| arg == "--width"));
+}
+
+#[test]
+fn demo_user() {
+    let args = game_args(&Features::new().with(Features::IS_DEMO_USER));
+    assert_eq!(args.iter().filter(|arg| *arg == "--demo").count(), 1);
+    assert!(!args.iter().any(|arg| arg == "--width"));
+}
+
+#[test]
+fn custom_resolution() {
+    let args = game_args(&Features::new().with(Features::HAS_CUSTOM_RESOLUTION));
+    assert!(contains_sequence(
+        &args,
+        &[
+            "--width",
+            "${resolution_width}",
+            "--height",
+            "${resolution_height}"
+        ]
+    ));
+}
+
+#[test]
+fn quick_play_multiplayer() {
+    let features = Features::new()
+        .with(Features::HAS_QUICK_PLAYS_SUPPORT)
+        .with(Features::IS_QUICK_PLAY_MULTIPLAYER);
+    let args = game_args(&features);
+
+    assert!(contains_sequence(
+        &args,
+        &["--quickPlayPath", "${quickPlayPath}"]
+    ));
+    assert!(contains_sequence(
+        &args,
+        &["--quickPlayMultiplayer", "${quickPlayMultiplayer}"]
+    ));
+    assert!(!args.iter().any(|arg| arg == "--quickPlaySingleplayer"));
+    assert!(!args.iter().any(|arg| arg == "--quickPlayRealms"));
+}
+
+#[test]
+fn jvm_arguments_follow_os_rules() {
+    let (jvm, _) = arguments().into_raw(&Features::new());
+
+    assert!(contains_sequence(&jvm, &["-cp", "${classpath}"]));
+    assert_eq!(
+        jvm.iter().any(|arg| arg == "-XstartOnFirstThread"),
+        cfg!(target_os = "macos")
+    );
+    assert_eq!(
+        jvm.iter().any(|arg| arg == "-Xss1M"),
+        cfg!(target_arch = "x86")
+    );
+}
+
+#[test]
+fn disallowed_feature_rule() {
+    let rule: Rule =
+        serde_json::from_str(r#"{ "action": "disallow", "features": { "is_demo_user": true } }"#)
+            .unwrap();
+
+    assert!(rule.is_allowed(&Features::new()));
+    assert!(!rule.is_allowed(&Features::new().with(Features::IS_DEMO_USER)));
+}
+
+#[test]
+fn feature_expected_to_be_disabled() {
+    let rule: Rule = serde_json::from_str(
+        r#"{ "action": "allow", "features": { "has_custom_resolution": false } }"#,
+    )
+    .unwrap();
+
+    assert!(rule.is_allowed(&Features::new()));
+    assert!(!rule.is_allowed(&Features::new().with(Features::HAS_CUSTOM_RESOLUTION)));
+}
+
+#[test]
+fn library_rules() {
+    let library: Library = serde_json::from_str(
+        r#"{
+            "downloads": {
+                "artifact": {
+                    "path": "ca/weblite/java-objc-bridge/1.1/java-objc-bridge-1.1.jar",
+                    "sha1": "1227f9e0666314f9de41477e3ec277e542ed7f7b",
+                    "size": 1330045,
+                    "url": "https://libraries.minecraft.net/ca/weblite/java-objc-bridge/1.1/java-objc-bridge-1.1.jar"
+                }
+            },
+            "name": "ca.weblite:java-objc-bridge:1.1",
+            "rules": [{ "action": "allow", "os": { "name": "osx" } }]
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        library.is_allowed(&Features::new()),
+        cfg!(target_os = "macos")
+    );
+}
+
+#[test]
+fn supported_features() {
+    let arguments = arguments();
+    assert!(arguments.supports_feature(Features::IS_QUICK_PLAY_REALMS));
+    assert!(arguments.supports_feature(Features::HAS_CUSTOM_RESOLUTION));
+    assert!(!arguments.supports_feature("is_unknown_feature"));
+
+    let legacy = Arguments::MinecraftArgs("--username ${auth_player_name}".to_string());
+    assert!(!legacy.supports_feature(Features::IS_QUICK_PLAY_MULTIPLAYER));
+}
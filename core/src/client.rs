@@ -1,207 +1,402 @@
-use bytes::Bytes;
-use crab_launcher_api::meta::client::{Client, Download, Index};
-use futures::{stream::FuturesUnordered, StreamExt};
-use sha1::{Digest, Sha1};
+use crab_launcher_api::meta::{
+    client::{Client, Download, Index, Library, Object},
+    utils::Platform,
+};
 
-use crate::utils::{self, download::DownloadError, errors::CoreError, zip::ZipExtractor};
+use crate::scheduler::Scheduler;
+use crate::utils::{
+    download::{DownloadError, FileDigest},
+    errors::CoreError,
+    zip::{Glob, ZipExtractor},
+};
 use std::{
-    fs::{self, File},
-    os::unix::fs::MetadataExt,
-    path::{Path, PathBuf},
+    fs,
+    path::{Component, Path, PathBuf},
 };
 
-#[inline(always)]
-async fn download_in_inner(download: &Download, full_path: &Path) -> Result<(), DownloadError> {
-    // validates that `file` isn't corrupted
-    let verify_data = |file: &mut File, size: usize, sha1: &str| {
-        if file.metadata().is_ok_and(|x| (x.size() as usize) < size) {
-            println!("what?");
-            return false;
-        }
+/// The state of a file on disk compared to the file it is expected to be
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileStatus {
+    Valid,
+    Missing,
+    Corrupted,
+}
+
+/// A file required by an installed client, with everything needed to verify and (re)download it
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequiredFile {
+    pub path: PathBuf,
+    pub url: String,
+    pub expected: FileDigest,
+}
 
-        let mut hasher = Sha1::new();
-        let Ok(_) = std::io::copy(file, &mut hasher) else {
-            return false;
+impl RequiredFile {
+    /// the file described by `download`, `path` is joined with the download's sub path if it has one
+    fn from_download(download: &Download, path: &Path) -> Self {
+        let path = match download.sub_path {
+            Some(ref child) => path.join(child),
+            None => path.to_path_buf(),
         };
-        let hash = hasher.finalize();
-        if hash.as_slice() != sha1.as_bytes() {
-            return false;
+        Self {
+            path,
+            url: download.url.clone(),
+            expected: FileDigest {
+                size: download.size as u64,
+                sha1: download.sha1.clone(),
+            },
         }
+    }
 
-        true
-    };
+    /// each object is a file with its hash as name, in a subdirectory named after the first 2 letters of the hash
+    fn from_object(assets_root: &Path, object: &Object) -> Self {
+        let dir_name = &object.hash[0..2];
+        Self {
+            path: assets_root
+                .join("objects")
+                .join(dir_name)
+                .join(&object.hash),
+            url: format!(
+                "https://resources.download.minecraft.net/{dir_name}/{}",
+                object.hash
+            ),
+            expected: FileDigest {
+                size: object.size as u64,
+                sha1: object.hash.clone(),
+            },
+        }
+    }
 
-    if File::open(full_path)
-        .is_ok_and(|mut f| verify_data(&mut f, download.size as usize, &download.sha1))
-    {
-        return Ok(());
+    /// checks the size and SHA-1 of the file on disk
+    pub fn status(&self) -> FileStatus {
+        let Ok(metadata) = fs::metadata(&self.path) else {
+            return FileStatus::Missing;
+        };
+        // a wrong size is enough to tell the file apart without hashing it
+        if metadata.len() != self.expected.size {
+            return FileStatus::Corrupted;
+        }
+        match FileDigest::of(&self.path) {
+            Ok(digest) if digest.matches(&self.expected) => FileStatus::Valid,
+            _ => FileStatus::Corrupted,
+        }
     }
+}
+
+fn index_path(assets_root: &Path, client: &Client) -> PathBuf {
+    assets_root
+        .join("indexes")
+        .join(format!("{}.json", client.assets))
+}
+
+/// returns every file required by `client` installed in the profile directory `path`
+/// asset objects are listed from the asset index on disk, so they are only included if the index itself is valid
+pub fn required_files(
+    assets_root: &Path,
+    libs_root: &Path,
+    client: &Client,
+    path: &Path,
+) -> Vec<RequiredFile> {
+    let mut files = vec![RequiredFile::from_download(
+        &client.downloads.client,
+        &path.join("client.jar"),
+    )];
 
-    let data = utils::download::get(&download.url).await?;
+    for lib in client.libs() {
+        for jar in lib.jars(Platform::CURRENT) {
+            files.push(RequiredFile::from_download(jar, libs_root));
+        }
+    }
 
-    if let Some(parent) = full_path.parent() {
-        fs::create_dir_all(parent)?;
+    let index = RequiredFile::from_download(&client.asset_index, &index_path(assets_root, client));
+    if index.status() == FileStatus::Valid {
+        files.extend(
+            index_objects(&index.path)
+                .iter()
+                .map(|object| RequiredFile::from_object(assets_root, object)),
+        );
     }
-    fs::write(full_path, &data)?;
-    Ok(())
+    files.push(index);
+    files
 }
 
-/// [`download_in`] but reads the file after download or reads it if it is simply already downloaded
-async fn get_download_in(download: &Download, path: &Path) -> Result<Bytes, DownloadError> {
-    let full_path = if let Some(ref child) = download.sub_path {
-        &path.join(child)
-    } else {
-        path
-    };
+fn read_index(path: &Path) -> Option<Index> {
+    let data = fs::read(path).ok()?;
+    serde_json::from_slice(&data).ok()
+}
 
-    download_in_inner(download, full_path).await?;
-    Ok(Bytes::from(
-        fs::read(full_path).expect("get_download_in: failed to read downloaded file"),
-    ))
+/// returns the objects listed by the asset index at `path`, each object only once
+fn index_objects(path: &Path) -> Vec<Object> {
+    let mut objects: Vec<_> = read_index(path)
+        .map(|index| index.objects.into_values().collect())
+        .unwrap_or_default();
+    // the same object may be used by multiple assets
+    objects.sort_by(|a: &Object, b| a.hash.cmp(&b.hash));
+    objects.dedup_by(|a, b| a.hash == b.hash);
+    objects
 }
 
-/// Downloads `download` to `path`
-async fn download_in(download: &Download, path: &Path) -> Result<(), DownloadError> {
-    let full_path = if let Some(ref child) = download.sub_path {
-        &path.join(child)
-    } else {
-        path
-    };
-    download_in_inner(download, full_path).await
-}
-
-/// TODO: benchmark
-#[inline(always)]
-/// Downloads the given `items`` in parallel, up to the given max `download_max`` using the given download function `download`
-async fn download_futures<T, F, R, I>(to_download: I, download_max: usize, download: F) -> Vec<R>
-where
-    I: Iterator<Item = T>,
-    F: AsyncFn(T) -> R,
-{
-    let (size_0, size_1) = to_download.size_hint();
-    let size_hint = size_1.unwrap_or(size_0);
-
-    let mut outputs = Vec::with_capacity(size_hint.min(10));
-    let mut futures = FuturesUnordered::new();
-
-    for item in to_download {
-        futures.push(download(item));
-        if futures.len() == download_max {
-            let next = futures.next().await.unwrap();
-            outputs.push(next);
+/// returns the files of the shared libraries and assets directories used by `client`
+/// unlike [`required_files`], the objects of an asset index are listed even if the index is corrupted
+pub(crate) fn shared_files(assets_root: &Path, libs_root: &Path, client: &Client) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for lib in client.libs() {
+        for jar in lib.jars(Platform::CURRENT) {
+            files.push(RequiredFile::from_download(jar, libs_root).path);
         }
     }
 
-    while let Some(future) = futures.next().await {
-        outputs.push(future);
+    let index = index_path(assets_root, client);
+    files.extend(
+        index_objects(&index)
+            .iter()
+            .map(|object| RequiredFile::from_object(assets_root, object).path),
+    );
+    // the virtual assets directory is shared by every profile using the index, unlike `resources`
+    if let Some(index) = read_index(&index).filter(|index| index.is_virtual) {
+        let dir = virtual_assets_dir(assets_root, client);
+        files.extend(
+            index
+                .objects
+                .keys()
+                .filter_map(|name| asset_path(&dir, name)),
+        );
     }
-
-    outputs
+    files.push(index);
+    files
 }
 
-async fn download_assets(assets_root: &Path, client: &Client) -> Result<(), DownloadError> {
-    let id = &client.assets;
-    println!("Downloading assets for {}...", id);
-    let indexes_dir = assets_root.join("indexes");
-    let indexes_path = indexes_dir.join(format!("{}.json", id));
-    let download = get_download_in(&client.asset_index, &indexes_path).await?;
+fn virtual_assets_dir(assets_root: &Path, client: &Client) -> PathBuf {
+    assets_root.join("virtual").join(&client.assets)
+}
 
-    // downloading objects
-    let index: Index = serde_json::from_slice(&download).unwrap();
-    let objects = index.objects;
+/// the directory the assets of `index` are laid out in by name, `None` if they are read from the objects directory
+fn named_assets_dir(
+    assets_root: &Path,
+    client: &Client,
+    index: &Index,
+    path: &Path,
+) -> Option<PathBuf> {
+    if index.map_to_resources {
+        Some(path.join("resources"))
+    } else if index.is_virtual {
+        Some(virtual_assets_dir(assets_root, client))
+    } else {
+        None
+    }
+}
 
-    // each object is a file with a hash as name, in a subdirectory with the first 2 letters of the hash which exists in the assets folder
-    let download_object =
-        async |object: crab_launcher_api::meta::client::Object| -> Result<(), DownloadError> {
-            let dir_name = &object.hash[0..2];
-            let dir = assets_root.join("objects").join(dir_name);
-            let path = dir.join(&object.hash);
+/// the path of the asset called `name` inside `dir`, `None` if the name would escape `dir`
+fn asset_path(dir: &Path, name: &str) -> Option<PathBuf> {
+    let name = Path::new(name);
+    name.components()
+        .all(|component| matches!(component, Component::Normal(_)))
+        .then(|| dir.join(name))
+}
 
-            if path.exists() {
-                return Ok(());
-            }
+/// the directory `${game_assets}` points to for `client` installed in the profile directory `path`
+/// versions predating the objects directory read their assets by name, see [`place_assets`]
+pub fn game_assets_dir(assets_root: &Path, client: &Client, path: &Path) -> PathBuf {
+    read_index(&index_path(assets_root, client))
+        .and_then(|index| named_assets_dir(assets_root, client, &index, path))
+        .unwrap_or_else(|| assets_root.to_path_buf())
+}
 
-            fs::create_dir_all(&dir)?;
-            let data = utils::download::get(&format!(
-                "https://resources.download.minecraft.net/{dir_name}/{}",
-                object.hash
-            ))
-            .await?;
+/// lays out the assets of `client` by name for the versions that need it, see [`game_assets_dir`]
+/// each asset is hardlinked to its object, or copied if the filesystem doesn't support hardlinks
+pub fn place_assets(
+    assets_root: &Path,
+    client: &Client,
+    path: &Path,
+) -> Result<(), CoreError<'static>> {
+    let Some(index) = read_index(&index_path(assets_root, client)) else {
+        return Ok(());
+    };
+    let Some(dir) = named_assets_dir(assets_root, client, &index, path) else {
+        return Ok(());
+    };
 
-            fs::write(path, data)?;
-            Ok(())
+    for (name, object) in &index.objects {
+        let Some(target) = asset_path(&dir, name) else {
+            continue;
         };
+        let source = RequiredFile::from_object(assets_root, object);
+        if is_placed(&target, &source) {
+            continue;
+        }
 
-    let iter = objects.into_iter();
-    let iter = iter.map(|(_, object)| object);
-    let outputs = download_futures(iter, 20, download_object).await;
-    for (i, output) in outputs.into_iter().enumerate() {
-        if let Err(err) = output {
-            println!("Failed to download object indexed {i}: {err:?}");
-            return Err(err);
+        let source = source.path;
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let _ = fs::remove_file(&target);
+        if fs::hard_link(&source, &target).is_err() {
+            fs::copy(&source, &target)?;
         }
     }
-
-    println!("Downloaded assets for {}", id);
     Ok(())
 }
 
-/// installs the libraries required by current client and uses the given path as the base
-/// profile directory
-async fn install_libs(
+/// whether the asset at `target` is `object`, either hardlinked to it or an intact copy of it
+fn is_placed(target: &Path, object: &RequiredFile) -> bool {
+    let (Ok(placed), Ok(source)) = (fs::metadata(target), fs::metadata(&object.path)) else {
+        return false;
+    };
+    if is_same_file(&placed, &source) {
+        return true;
+    }
+    placed.len() == object.expected.size
+        && FileDigest::of(target).is_ok_and(|digest| digest.matches(&object.expected))
+}
+
+#[cfg(unix)]
+fn is_same_file(a: &fs::Metadata, b: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+/// copies are always hashed where hardlinks can't be told apart
+#[cfg(not(unix))]
+fn is_same_file(_: &fs::Metadata, _: &fs::Metadata) -> bool {
+    false
+}
+
+/// returns every file required by `client` that is missing or corrupted, see [`required_files`]
+pub fn verify_client(
+    assets_root: &Path,
     libs_root: &Path,
     client: &Client,
     path: &Path,
+) -> Vec<(RequiredFile, FileStatus)> {
+    required_files(assets_root, libs_root, client, path)
+        .into_iter()
+        .map(|file| {
+            let status = file.status();
+            (file, status)
+        })
+        .filter(|(_, status)| *status != FileStatus::Valid)
+        .collect()
+}
+
+/// downloads again every file required by `client` that is missing or corrupted, returns the repaired files
+/// every file is verified once, then only the broken ones are downloaded, the scheduler verifying each download
+pub async fn repair_client(
+    scheduler: &Scheduler,
+    assets_root: &Path,
+    libs_root: &Path,
+    client: &Client,
+    path: &Path,
+) -> Result<Vec<RequiredFile>, DownloadError> {
+    let mut repaired: Vec<_> = verify_client(assets_root, libs_root, client, path)
+        .into_iter()
+        .map(|(file, _)| file)
+        .collect();
+    let index = index_path(assets_root, client);
+    let index_repaired = repaired.iter().any(|file| file.path == index);
+    scheduler.run(repaired.clone()).await?;
+
+    // a broken asset index hides the objects it lists, so they can only be verified once it is repaired
+    if index_repaired {
+        let objects: Vec<_> = index_objects(&index)
+            .iter()
+            .map(|object| RequiredFile::from_object(assets_root, object))
+            .filter(|file| file.status() != FileStatus::Valid)
+            .collect();
+        scheduler.run(objects.clone()).await?;
+        repaired.extend(objects);
+    }
+    Ok(repaired)
+}
+
+/// the natives of `lib` to extract and the patterns of the entries to skip
+fn native_extract(lib: &Library) -> Option<(&Download, Vec<Glob>)> {
+    let native = lib.platform_native()?;
+    let mut exclude: Vec<_> = lib
+        .extract
+        .iter()
+        .flat_map(|rules| rules.exclude.iter().flatten())
+        .map(|pattern| Glob::new(&pattern.to_string_lossy()))
+        .collect();
+    // the natives published as their own library since 1.19 have no extract rules but still carry a manifest
+    if lib.is_native_artifact() {
+        exclude.push(Glob::new("META-INF/"));
+    }
+    Some((native, exclude))
+}
+
+/// the directory the natives of `client` are extracted to inside `root`, the natives directory of a profile
+/// it is named after `version` and a hash of the natives, so a profile changing version or natives gets a new directory
+pub(crate) fn natives_dir(client: &Client, version: &str, root: &Path) -> PathBuf {
+    let mut natives = String::new();
+    for lib in client.libs() {
+        if let Some((native, exclude)) = native_extract(lib) {
+            let exclude: Vec<_> = exclude.iter().map(Glob::to_string).collect();
+            natives.push_str(&format!("{} {}\n", native.sha1, exclude.join(" ")));
+        }
+    }
+    let hash = FileDigest::of_bytes(natives.as_bytes()).sha1;
+    root.join(format!("{version}-{}", &hash[..12]))
+}
+
+/// extracts the natives required by `client` into their directory inside `root`, see [`natives_dir`]
+/// extraction is skipped if it was already done, and the directories of other versions are deleted
+pub(crate) fn extract_natives(
+    libs_root: &Path,
+    client: &Client,
+    version: &str,
+    root: &Path,
 ) -> Result<(), CoreError<'static>> {
-    println!("Downloading libraries...");
-    let download_lib =
-        async |lib: &crab_launcher_api::meta::client::Library| -> Result<(), CoreError<'static>> {
-            // downloading lib
-            if let Some(ref artifact) = lib.downloads.artifact {
-                download_in(artifact, libs_root).await?;
-            }
-            // downloading natives required by lib
-            if let Some(native) = lib.platform_native() {
-                let bytes = get_download_in(native, libs_root).await?;
-
-                if let Some(ref extract_rules) = lib.extract {
-                    let natives_dir = path.join(".natives");
-
-                    let exclude = extract_rules.exclude.as_deref().unwrap_or_default();
-                    let paths = exclude.iter().map(PathBuf::as_path).collect::<Vec<_>>();
-                    let zip = ZipExtractor::new(&bytes).exclude(&paths);
-
-                    zip.extract(&natives_dir)?;
-                }
-            }
-            Ok(())
-        };
+    let natives_dir = natives_dir(client, version, root);
+    let marker = natives_dir.join(".extracted");
+    if !marker.exists() {
+        // an interrupted extraction may have left files behind
+        if natives_dir.exists() {
+            fs::remove_dir_all(&natives_dir)?;
+        }
+        fs::create_dir_all(&natives_dir)?;
 
-    let outputs = download_futures(client.libs(), 5, download_lib).await;
-    for (i, output) in outputs.into_iter().enumerate() {
-        if let Err(err) = output {
-            println!("Failed to download library indexed {i}: {err:?}");
-            return Err(err);
+        for lib in client.libs() {
+            let Some((native, exclude)) = native_extract(lib) else {
+                continue;
+            };
+            let native_path = RequiredFile::from_download(native, libs_root).path;
+            ZipExtractor::open(&native_path)?
+                .exclude(exclude)
+                .extract(&natives_dir)?;
         }
+        fs::write(&marker, "")?;
     }
 
-    println!("Done downloading libraries");
+    for entry in fs::read_dir(root)? {
+        let entry = entry?;
+        let path = entry.path();
+        if path == natives_dir {
+            continue;
+        }
+        // only directories are extracted natives, anything else was left there by something else
+        if entry.file_type()?.is_dir() {
+            fs::remove_dir_all(path)?;
+        } else {
+            fs::remove_file(path)?;
+        }
+    }
     Ok(())
 }
 
-/// Installs the given client into the given path, downloading all the required assets and libraries
+/// Installs the given client into the given path, downloading all the required assets and libraries through `scheduler`
+/// natives aren't extracted, each profile extracts them into its own natives directory
 pub async fn install_client(
+    scheduler: &Scheduler,
     assets_root: &Path,
     libs_root: &Path,
-    client: Client,
+    client: &Client,
     path: &Path,
 ) -> Result<(), CoreError<'static>> {
-    download_assets(assets_root, &client).await?;
-    install_libs(libs_root, &client, path).await?;
-    println!("Downloading client...");
-    let client_path = path.join("client.jar");
-    // downloading client.jar
-    download_in(&client.downloads.client, &client_path).await?;
-    println!("Done downloading client");
-    Ok(())
+    // the asset index lists the objects to download, so it is needed before anything else can be queued
+    let index = RequiredFile::from_download(&client.asset_index, &index_path(assets_root, client));
+    scheduler.run(vec![index]).await?;
+
+    scheduler
+        .run(required_files(assets_root, libs_root, client, path))
+        .await?;
+    place_assets(assets_root, client, path)
 }
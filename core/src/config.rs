@@ -1,80 +1,705 @@
-use serde::{Deserialize, Serialize};
-use velcro::hash_map_from;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
 
-use std::collections::HashMap;
-use std::fs::File;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fmt::Display;
+use std::fs::{self, File};
 use std::io::{BufReader, Seek, SeekFrom};
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
+use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use crate::java;
+use crate::scheduler;
+use crate::utils::download::DownloadOptions;
+use crate::utils::errors::{ConfigError, CoreError};
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub struct Config(HashMap<String, String>);
+/// A value that can be stored in a [`Config`] entry
+pub trait ConfigValue: Sized {
+    /// parses a value given by the user, returns the reason on failure
+    fn parse(value: &str) -> Result<Self, String>;
+    /// formats the value the same way [`ConfigValue::parse`] expects it
+    fn display(&self) -> String;
+}
 
-impl Config {
-    fn create_default() -> Result<Self, std::io::Error> {
-        let java_list = java::java_manager()
-            .latest()
-            .ok_or(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                "No Java found",
-            ))?
-            .clone();
+impl ConfigValue for u32 {
+    fn parse(value: &str) -> Result<Self, String> {
+        value
+            .trim()
+            .parse()
+            .map_err(|_| "expected a positive whole number".to_string())
+    }
+
+    fn display(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl ConfigValue for bool {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value.trim() {
+            "true" | "yes" | "1" => Ok(true),
+            "false" | "no" | "0" => Ok(false),
+            _ => Err("expected `true` or `false`".to_string()),
+        }
+    }
+
+    fn display(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl ConfigValue for String {
+    fn parse(value: &str) -> Result<Self, String> {
+        Ok(value.to_string())
+    }
+
+    fn display(&self) -> String {
+        self.clone()
+    }
+}
+
+impl ConfigValue for PathBuf {
+    fn parse(value: &str) -> Result<Self, String> {
+        if value.is_empty() {
+            return Err("expected a path".to_string());
+        }
+        Ok(PathBuf::from(value))
+    }
+
+    fn display(&self) -> String {
+        Path::display(self).to_string()
+    }
+}
+
+/// A list of arguments written as a single shell-like string, e.g. `-XX:+UseG1GC "-Dfoo=bar baz"`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Args(Vec<String>);
+
+impl Args {
+    pub fn as_slice(&self) -> &[String] {
+        &self.0
+    }
+}
+
+impl ConfigValue for Args {
+    fn parse(value: &str) -> Result<Self, String> {
+        shell_words::split(value)
+            .map(Self)
+            .map_err(|err| format!("expected shell-like arguments, {err}"))
+    }
+
+    fn display(&self) -> String {
+        shell_words::join(&self.0)
+    }
+}
+
+impl Serialize for Args {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.display())
+    }
+}
+
+impl<'de> Deserialize<'de> for Args {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // a list of arguments is accepted as is, a string is parsed by `lenient` before reaching here
+        Vec::<String>::deserialize(deserializer).map(Self)
+    }
+}
+
+/// A list of paths written as a single shell-like string, e.g. `/etc/ssl/internal.pem "/opt/my certs/ca.pem"`
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Paths(Vec<PathBuf>);
+
+impl Paths {
+    pub fn as_slice(&self) -> &[PathBuf] {
+        &self.0
+    }
+}
+
+impl ConfigValue for Paths {
+    fn parse(value: &str) -> Result<Self, String> {
+        let words = Args::parse(value)?;
+        Ok(Self(words.0.into_iter().map(PathBuf::from).collect()))
+    }
+
+    fn display(&self) -> String {
+        let paths: Vec<_> = self
+            .0
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        shell_words::join(paths)
+    }
+}
+
+/// Environment variables written as shell-like `KEY=VALUE` pairs, e.g. `MESA_GL_VERSION_OVERRIDE=4.5 "FOO=a b"`
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct EnvVars(BTreeMap<String, String>);
+
+impl EnvVars {
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.0.iter()
+    }
+}
+
+impl ConfigValue for EnvVars {
+    fn parse(value: &str) -> Result<Self, String> {
+        let words = Args::parse(value)?;
+        words
+            .0
+            .into_iter()
+            .map(|word| match word.split_once('=') {
+                Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+                _ => Err(format!("expected KEY=VALUE, found `{word}`")),
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    fn display(&self) -> String {
+        let pairs: Vec<String> = self.iter().map(|(k, v)| format!("{k}={v}")).collect();
+        shell_words::join(pairs)
+    }
+}
+
+/// Url prefixes with the mirrors they are rewritten to, written as shell-like `PREFIX=MIRROR[,MIRROR...]` pairs
+/// e.g. `https://libraries.minecraft.net/=https://cache.example.com/libraries/,https://libraries.minecraft.net/`
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Mirrors(BTreeMap<String, Vec<String>>);
+
+impl ConfigValue for Mirrors {
+    fn parse(value: &str) -> Result<Self, String> {
+        let words = Args::parse(value)?;
+        words
+            .0
+            .into_iter()
+            .map(|word| match word.split_once('=') {
+                Some((prefix, mirrors)) if !prefix.is_empty() && !mirrors.is_empty() => Ok((
+                    prefix.to_string(),
+                    mirrors.split(',').map(str::to_string).collect(),
+                )),
+                _ => Err(format!(
+                    "expected PREFIX=MIRROR[,MIRROR...], found `{word}`"
+                )),
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    fn display(&self) -> String {
+        let pairs: Vec<String> = self
+            .0
+            .iter()
+            .map(|(prefix, mirrors)| format!("{prefix}={}", mirrors.join(",")))
+            .collect();
+        shell_words::join(pairs)
+    }
+}
+
+/// deserializes a typed entry, also accepting strings so config files written before entries were typed keep working
+fn lenient<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: ConfigValue + DeserializeOwned,
+{
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::Null => Ok(None),
+        serde_json::Value::String(value) => {
+            T::parse(&value).map(Some).map_err(serde::de::Error::custom)
+        }
+        value => serde_json::from_value(value)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+    }
+}
+
+/// Declares the known entries of [`Config`] with their types and optional defaults
+macro_rules! config_entries {
+    ($($(#[doc = $doc:literal])* $name:ident: $ty:ty $(= $default:expr)?,)*) => {
+        #[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+        pub struct Config {
+            $(
+                $(#[doc = $doc])*
+                #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "lenient")]
+                $name: Option<$ty>,
+            )*
+            /// free-form entries which aren't known by the launcher, used to fill `${placeholders}` in the launch arguments
+            #[serde(flatten)]
+            extra: HashMap<String, String>,
+        }
+
+        impl Config {
+            /// the names of all the entries known by the launcher
+            pub const KEYS: &'static [&'static str] = &[$(stringify!($name)),*];
+
+            /// returns a config made of the default value of every entry that has one
+            pub fn defaults() -> Self {
+                #[allow(unused_mut)]
+                let mut config = Self::default();
+                $($(config.$name = Some($default.into());)?)*
+                config
+            }
+
+            $(
+                $(#[doc = $doc])*
+                pub fn $name(&self) -> Option<&$ty> {
+                    self.$name.as_ref()
+                }
+            )*
+
+            /// returns the value of `entry` formatted as a string
+            pub fn get(&self, entry: &str) -> Option<String> {
+                match entry {
+                    $(stringify!($name) => self.$name.as_ref().map(ConfigValue::display),)*
+                    _ => self.extra.get(entry).cloned(),
+                }
+            }
+
+            /// parses `value` according to the type of `entry` and sets `entry` to it
+            /// unknown entries are kept as free-form strings
+            pub fn set(&mut self, entry: &str, value: &str) -> Result<(), ConfigError> {
+                match entry {
+                    $(stringify!($name) => {
+                        let parsed = <$ty as ConfigValue>::parse(value).map_err(|reason| {
+                            ConfigError::InvalidValue {
+                                entry: entry.to_string(),
+                                value: value.to_string(),
+                                reason,
+                            }
+                        })?;
+                        self.$name = Some(parsed);
+                    })*
+                    _ => {
+                        self.extra.insert(entry.to_string(), value.to_string());
+                    }
+                }
+                Ok(())
+            }
+
+            /// returns every entry that is set in this config with its value formatted as a string
+            pub fn entries(&self) -> Vec<(String, String)> {
+                let mut entries = Vec::new();
+                $(
+                    if let Some(value) = &self.$name {
+                        entries.push((stringify!($name).to_string(), value.display()));
+                    }
+                )*
+                let mut extra: Vec<_> = self.extra.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+                extra.sort();
+                entries.extend(extra);
+                entries
+            }
+
+            pub fn remove(&mut self, entry: &str) {
+                match entry {
+                    $(stringify!($name) => self.$name = None,)*
+                    _ => {
+                        self.extra.remove(entry);
+                    }
+                }
+            }
+
+            /// Returns a new read-only config with the entries of `self` and `other` merged, favoring `self` over `other`
+            pub fn merge(self, mut other: Self) -> Self {
+                $(other.$name = self.$name.or(other.$name);)*
+                other.extra.extend(self.extra);
+                other
+            }
+        }
+    };
+}
+
+config_entries! {
+    /// the minimum amount of RAM given to the game in MiB
+    min_ram: u32 = 512u32,
+    /// the maximum amount of RAM given to the game in MiB
+    max_ram: u32 = 2048u32,
+    /// the java executable used to launch the game
+    current_java_path: PathBuf,
+    auth_player_name: String = "dev",
+    auth_access_token: String = "0",
+    /// the number of times a failed download is attempted again
+    download_retries: u32 = 5u32,
+    /// the number of seconds a download may wait to receive data
+    download_timeout: u32 = 30u32,
+    /// the number of seconds a download may wait to connect to a server
+    connect_timeout: u32 = 10u32,
+    /// the number of files downloaded at once
+    max_downloads: u32 = scheduler::MAX_DOWNLOADS,
+    /// the number of files downloaded at once from the same server
+    max_downloads_per_host: u32 = scheduler::MAX_DOWNLOADS_PER_HOST,
+    /// the proxy used to download `http` urls, e.g. `http://proxy.example.com:3128`
+    http_proxy: String,
+    /// the proxy used to download `https` urls
+    https_proxy: String,
+    /// a comma separated list of the hosts that are reached without the proxies
+    no_proxy: String,
+    /// PEM files with certificates trusted on top of the system's, e.g. an internal CA
+    ca_bundles: Paths,
+    /// the number of seconds the cached version manifest is used without checking for a newer one
+    manifest_ttl: u32 = 600u32,
+    /// urls starting with one of the prefixes are downloaded from its mirrors instead, trying them in order
+    mirrors: Mirrors,
+    /// the url of the index of the Java runtimes downloaded when no installed Java matches a version
+    java_runtimes_url: String,
+    /// launches the game in demo mode
+    demo: bool = false,
+    /// the width of the game window, requires `resolution_height`
+    resolution_width: u32,
+    /// the height of the game window, requires `resolution_width`
+    resolution_height: u32,
+    /// launches the game in fullscreen
+    fullscreen: bool = false,
+    /// joins the server at the given `host[:port]` once the game starts
+    quick_play_multiplayer: String,
+    /// opens the singleplayer world with the given name once the game starts
+    quick_play_singleplayer: String,
+    /// joins the realm with the given id once the game starts
+    quick_play_realms: String,
+    /// arguments passed to the JVM after the arguments required by the game, may contain `${placeholders}`
+    extra_jvm_args: Args,
+    /// arguments passed to the game after the arguments required by the game, may contain `${placeholders}`
+    extra_game_args: Args,
+    /// a command the java command is appended to, e.g. `gamemoderun` or `mangohud`
+    wrapper_command: Args,
+    /// environment variables set for the game and the launch hooks
+    env: EnvVars,
+    /// a shell command run before launching the game, the launch is aborted if it fails
+    pre_launch: String,
+    /// a shell command run after the game exits
+    post_exit: String,
+}
+
+/// the entries read once for the whole launcher before any profile is known, see [`LayeredConfig::check_layers`]
+const LAUNCHER_ENTRIES: &[&str] = &[
+    "download_retries",
+    "download_timeout",
+    "connect_timeout",
+    "max_downloads",
+    "max_downloads_per_host",
+    "http_proxy",
+    "https_proxy",
+    "no_proxy",
+    "ca_bundles",
+    "mirrors",
+    "manifest_ttl",
+    "java_runtimes_url",
+];
+
+/// the entries launching straight into a game, only one of them can be set at a time
+const QUICK_PLAY_ENTRIES: [&str; 3] = [
+    "quick_play_multiplayer",
+    "quick_play_singleplayer",
+    "quick_play_realms",
+];
 
-        Ok(Self(hash_map_from! {
-            "min_ram": "512",
-            "max_ram": "2048",
-            "auth_player_name": "dev",
-            "auth_access_token": "0",
-            "current_java_path": java_list.path,
-        }))
+/// the prefix of the environment variables that override config entries
+const ENV_PREFIX: &str = "CRAB_";
+
+/// The places a config entry can come from, from the lowest to the highest precedence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Layer {
+    Default,
+    Global,
+    Profile,
+    Env,
+    Override,
+}
+
+impl Display for Layer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Layer::Default => "default",
+            Layer::Global => "global",
+            Layer::Profile => "profile",
+            Layer::Env => "environment",
+            Layer::Override => "--set",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A stack of configs where each entry is taken from the highest layer that sets it
+#[derive(Debug, Clone, Default)]
+pub struct LayeredConfig {
+    layers: Vec<(Layer, Config)>,
+}
+
+impl LayeredConfig {
+    /// adds `config` as `layer`, replacing any config previously given for that layer
+    pub fn with(mut self, layer: Layer, config: Config) -> Self {
+        self.layers.retain(|(l, _)| *l != layer);
+        self.layers.push((layer, config));
+        self.layers.sort_by_key(|(l, _)| *l);
+        self
+    }
+
+    /// returns the effective config, merging all the layers
+    /// the quick play entries exclude each other, so only the ones of the highest layer setting any of them are kept
+    pub fn resolve(&self) -> Config {
+        let quick_play_layer = self
+            .layers
+            .iter()
+            .rev()
+            .find(|(_, config)| config.has_quick_play())
+            .map(|(layer, _)| *layer);
+
+        self.layers
+            .iter()
+            .fold(Config::default(), |acc, (layer, config)| {
+                let mut config = config.clone();
+                if quick_play_layer.is_some_and(|quick_play_layer| quick_play_layer != *layer) {
+                    QUICK_PLAY_ENTRIES
+                        .iter()
+                        .for_each(|entry| config.remove(entry));
+                }
+                config.merge(acc)
+            })
+    }
+
+    /// checks that the entries applying to the whole launcher are only set in the layers the launcher reads them from,
+    /// the defaults, the global config and the environment
+    pub fn check_layers(&self) -> Result<(), ConfigError> {
+        for (layer, config) in &self.layers {
+            if !matches!(layer, Layer::Profile | Layer::Override) {
+                continue;
+            }
+            if let Some(entry) = LAUNCHER_ENTRIES
+                .iter()
+                .find(|entry| config.get(entry).is_some())
+            {
+                return Err(ConfigError::LauncherOnly(entry, *layer));
+            }
+        }
+        Ok(())
+    }
+
+    /// returns the layer the effective value of `entry` comes from
+    pub fn origin(&self, entry: &str) -> Option<Layer> {
+        self.layers
+            .iter()
+            .rev()
+            .find(|(_, config)| config.get(entry).is_some())
+            .map(|(layer, _)| *layer)
+    }
+
+    /// returns every effective entry with its value and the layer it comes from
+    pub fn entries(&self) -> Vec<(String, String, Layer)> {
+        self.resolve()
+            .entries()
+            .into_iter()
+            .filter_map(|(entry, value)| {
+                let layer = self.origin(&entry)?;
+                Some((entry, value, layer))
+            })
+            .collect()
     }
 }
 
 impl Config {
-    pub fn new(map: HashMap<String, String>) -> Self {
-        Self(map)
+    fn create_default() -> Self {
+        Self {
+            current_java_path: java::java_manager()
+                .latest()
+                .map(|java| PathBuf::from(&java.path)),
+            ..Default::default()
+        }
+    }
+
+    /// builds a config from the `CRAB_<ENTRY>` environment variables of the known entries, e.g. `CRAB_MAX_RAM`
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let mut config = Self::default();
+        for entry in Self::KEYS {
+            let var = format!("{ENV_PREFIX}{}", entry.to_uppercase());
+            if let Ok(value) = env::var(var) {
+                config.set(entry, &value)?;
+            }
+        }
+        Ok(config)
+    }
+
+    /// builds a config from `entry`, `value` pairs
+    pub fn from_pairs(pairs: &[(String, String)]) -> Result<Self, ConfigError> {
+        let mut config = Self::default();
+        for (entry, value) in pairs {
+            config.set(entry, value)?;
+        }
+        Ok(config)
     }
 
-    pub fn empty() -> Self {
-        Self(HashMap::new())
+    pub(crate) fn global_config_path(config_root: &Path) -> PathBuf {
+        config_root.join("config.json")
     }
 
-    fn global_config_path(launcher_root: &Path) -> PathBuf {
-        launcher_root.join("config.json")
+    /// reads and parses the config at `path`, returns `None` if there is no config at `path`
+    pub fn read(path: &Path) -> Result<Option<Self>, CoreError<'static>> {
+        let Ok(file) = File::open(path) else {
+            return Ok(None);
+        };
+        let reader = BufReader::new(file);
+        let config = serde_json::from_reader(reader)
+            .map_err(|err| ConfigError::Malformed(path.to_path_buf(), err))?;
+        Ok(Some(config))
     }
 
-    /// Reads the global config and returns a memory read-only copy of it
-    pub fn read_global(launcher_root: &Path) -> Result<Self, std::io::Error> {
-        let path = Self::global_config_path(launcher_root);
+    /// Reads the global config from the `config_root` directory and returns a memory read-only copy of it
+    pub fn read_global(config_root: &Path) -> Result<Self, CoreError<'static>> {
+        let path = Self::global_config_path(config_root);
 
-        let config = if !path.exists() {
-            let config = Self::create_default()?;
-            let file = File::create(path)?;
-            serde_json::to_writer_pretty(file, &config).unwrap();
-            config
-        } else {
-            let file = File::open(path)?;
-            let reader = BufReader::new(file);
-            serde_json::from_reader(reader).unwrap()
+        let config = match Self::read(&path)? {
+            Some(config) => config,
+            None => {
+                let config = Self::create_default();
+                fs::create_dir_all(config_root)?;
+                let file = File::create(path)?;
+                serde_json::to_writer_pretty(file, &config).unwrap();
+                config
+            }
         };
 
         Ok(config)
     }
 
-    pub fn get(&self, entry: &str) -> Option<&str> {
-        self.0.get(entry).map(|x| x.as_str())
+    /// returns the download options set by this config
+    pub fn download_options(&self) -> DownloadOptions {
+        let defaults = DownloadOptions::default();
+        DownloadOptions {
+            retries: self.download_retries().copied().unwrap_or(defaults.retries),
+            read_timeout: self
+                .download_timeout()
+                .map(|&secs| Duration::from_secs(secs.into()))
+                .unwrap_or(defaults.read_timeout),
+            connect_timeout: self
+                .connect_timeout()
+                .map(|&secs| Duration::from_secs(secs.into()))
+                .unwrap_or(defaults.connect_timeout),
+            http_proxy: self.http_proxy().cloned(),
+            https_proxy: self.https_proxy().cloned(),
+            no_proxy: self.no_proxy().cloned(),
+            ca_bundles: self
+                .ca_bundles()
+                .map(|paths| paths.0.clone())
+                .unwrap_or_default(),
+            mirrors: self
+                .mirrors()
+                .map(|mirrors| mirrors.0.clone().into_iter().collect())
+                .unwrap_or_default(),
+            ..defaults
+        }
+    }
+
+    /// whether the number set for `entry` is greater than 0, `None` if `entry` isn't a number or isn't set
+    fn positive(&self, entry: &str) -> Option<bool> {
+        let value = match entry {
+            "resolution_width" => self.resolution_width(),
+            "resolution_height" => self.resolution_height(),
+            "download_timeout" => self.download_timeout(),
+            "connect_timeout" => self.connect_timeout(),
+            "max_downloads" => self.max_downloads(),
+            "max_downloads_per_host" => self.max_downloads_per_host(),
+            _ => None,
+        };
+        value.map(|&value| value > 0)
+    }
+
+    /// checks that the value of `entry` is valid in this config
+    /// this should be called on a config merged with all the configs it is layered over
+    pub fn check(&self, entry: &str) -> Result<(), ConfigError> {
+        match entry {
+            "min_ram" | "max_ram" => {
+                let (Some(&min), Some(&max)) = (self.min_ram(), self.max_ram()) else {
+                    return Ok(());
+                };
+                if min == 0 {
+                    return Err(ConfigError::InvalidValue {
+                        entry: "min_ram".to_string(),
+                        value: min.to_string(),
+                        reason: "expected more than 0 MiB".to_string(),
+                    });
+                }
+                if min > max {
+                    return Err(ConfigError::RamRange { min, max });
+                }
+            }
+            "resolution_width" | "resolution_height" if self.positive(entry) == Some(false) => {
+                return Err(ConfigError::InvalidValue {
+                    entry: entry.to_string(),
+                    value: "0".to_string(),
+                    reason: "expected more than 0 pixels".to_string(),
+                });
+            }
+            "download_timeout" | "connect_timeout" if self.positive(entry) == Some(false) => {
+                return Err(ConfigError::InvalidValue {
+                    entry: entry.to_string(),
+                    value: "0".to_string(),
+                    reason: "expected more than 0 seconds".to_string(),
+                });
+            }
+            "max_downloads" | "max_downloads_per_host" if self.positive(entry) == Some(false) => {
+                return Err(ConfigError::InvalidValue {
+                    entry: entry.to_string(),
+                    value: "0".to_string(),
+                    reason: "expected at least 1 download".to_string(),
+                });
+            }
+            "ca_bundles" => {
+                for path in self.ca_bundles().map(Paths::as_slice).unwrap_or_default() {
+                    if !path.is_file() {
+                        return Err(ConfigError::InvalidValue {
+                            entry: entry.to_string(),
+                            value: path.display().to_string(),
+                            reason: "no such file".to_string(),
+                        });
+                    }
+                }
+            }
+            "current_java_path" => {
+                let path = self
+                    .current_java_path()
+                    .ok_or(ConfigError::Missing("current_java_path"))?;
+                let metadata =
+                    fs::metadata(path).map_err(|_| ConfigError::JavaNotFound(path.clone()))?;
+                if !metadata.is_file() || metadata.permissions().mode() & 0o111 == 0 {
+                    return Err(ConfigError::JavaNotExecutable(path.clone()));
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn has_quick_play(&self) -> bool {
+        QUICK_PLAY_ENTRIES
+            .iter()
+            .any(|entry| self.get(entry).is_some())
     }
 
-    /// Returns a new read-only config with the entries of `self` and `other` merged, favoring `self` over `other`
-    pub fn merge(self, mut other: Self) -> Self {
-        other.0.extend(self.0);
-        other
+    /// checks that every known entry is valid, see [`Config::check`]
+    /// also checks that entries which only work together are all set
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        Self::KEYS.iter().try_for_each(|entry| self.check(entry))?;
+        match (self.resolution_width(), self.resolution_height()) {
+            (Some(_), None) => return Err(ConfigError::Missing("resolution_height")),
+            (None, Some(_)) => return Err(ConfigError::Missing("resolution_width")),
+            _ => {}
+        }
+
+        let quick_plays = QUICK_PLAY_ENTRIES
+            .iter()
+            .filter(|entry| self.get(entry).is_some());
+        if quick_plays.count() > 1 {
+            return Err(ConfigError::Conflict(
+                "quick_play_multiplayer, quick_play_singleplayer and quick_play_realms",
+            ));
+        }
+        Ok(())
     }
 
-    pub fn into_mut<'a, 'b>(self, path: &'b Path) -> ConfigMut<'a> {
+    pub fn into_mut<'a>(self, path: &Path) -> Result<ConfigMut<'a>, std::io::Error> {
         ConfigMut::new(self, path)
     }
 }
@@ -87,21 +712,17 @@ pub struct ConfigMut<'a> {
 }
 
 impl<'a> ConfigMut<'a> {
-    pub fn new(config: Config, path: &Path) -> Self {
-        let fd = File::options().write(true).create(true).open(path).unwrap();
-        Self {
+    pub fn new(config: Config, path: &Path) -> Result<Self, std::io::Error> {
+        let fd = File::options()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        Ok(Self {
             config,
             fd,
             marker: PhantomData,
-        }
-    }
-
-    pub fn set(&mut self, entry: &str, value: String) {
-        self.0.insert(entry.to_string(), value);
-    }
-
-    pub fn remove(&mut self, entry: &str) {
-        self.0.remove(entry);
+        })
     }
 
     pub fn save(&mut self) {
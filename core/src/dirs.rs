@@ -0,0 +1,78 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+use crate::utils::errors::CoreError;
+
+/// the name of the launcher's directory inside of each XDG base directory
+const APP_DIR: &str = "crablauncher";
+/// the environment variable keeping every file of the launcher under a single directory, see [`LauncherDirs::from_env`]
+pub const ROOT_ENV: &str = "CRAB_LAUNCHER_ROOT";
+
+/// The directories the launcher stores its files in
+#[derive(Debug, Clone, PartialEq)]
+pub struct LauncherDirs {
+    /// profiles, libraries, assets and natives
+    data: PathBuf,
+    /// the global config.json
+    config: PathBuf,
+    /// the version manifest, Java runtimes and other files that can be re-downloaded at any time
+    cache: PathBuf,
+}
+
+impl LauncherDirs {
+    /// keeps every file of the launcher under a single `root` directory
+    pub fn rooted(root: &Path) -> Self {
+        Self {
+            data: root.to_path_buf(),
+            config: root.to_path_buf(),
+            cache: root.to_path_buf(),
+        }
+    }
+
+    /// keeps every file of the launcher in a `launcher` directory inside the current directory
+    pub fn portable() -> Self {
+        Self::rooted(Path::new("launcher"))
+    }
+
+    /// keeps every file under the directory given by [`ROOT_ENV`] if it is set, follows [`LauncherDirs::xdg`] otherwise
+    pub fn from_env() -> Result<Self, CoreError<'static>> {
+        match env::var_os(ROOT_ENV).filter(|root| !root.is_empty()) {
+            Some(root) => Ok(Self::rooted(Path::new(&root))),
+            None => Self::xdg(),
+        }
+    }
+
+    /// follows the XDG base directory specification
+    /// returns [`CoreError::NoHomeDir`] if neither the XDG variables nor `HOME` are set
+    pub fn xdg() -> Result<Self, CoreError<'static>> {
+        let home = env::var_os("HOME").map(PathBuf::from);
+        let base_dir = |var: &str, fallback: &str| -> Option<PathBuf> {
+            let dir = env::var_os(var)
+                .map(PathBuf::from)
+                // relative paths are invalid according to the specification and should be ignored
+                .filter(|dir| dir.is_absolute())
+                .or_else(|| home.as_ref().map(|home| home.join(fallback)))?;
+            Some(dir.join(APP_DIR))
+        };
+
+        Ok(Self {
+            data: base_dir("XDG_DATA_HOME", ".local/share").ok_or(CoreError::NoHomeDir)?,
+            config: base_dir("XDG_CONFIG_HOME", ".config").ok_or(CoreError::NoHomeDir)?,
+            cache: base_dir("XDG_CACHE_HOME", ".cache").ok_or(CoreError::NoHomeDir)?,
+        })
+    }
+
+    pub fn data(&self) -> &Path {
+        &self.data
+    }
+
+    pub fn config(&self) -> &Path {
+        &self.config
+    }
+
+    pub fn cache(&self) -> &Path {
+        &self.cache
+    }
+}
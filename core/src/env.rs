@@ -1,21 +1,44 @@
+use std::cell::Cell;
+use std::collections::HashSet;
+use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use crate::profiles::{Profile, ProfileMetadata, Profiles};
-use crate::utils::errors::CoreError;
+use crab_launcher_api::meta::manifest::Version;
+
+use crate::client::{self, FileStatus, RequiredFile};
+use crate::config::{Config, Layer, LayeredConfig};
+use crate::dirs::LauncherDirs;
+use crate::gc::{self, Garbage};
+use crate::profiles::{self, Profile, ProfileMetadata, Profiles};
+use crate::runtimes::{self, Runtimes};
+use crate::scheduler::{Progress, Scheduler, MAX_DOWNLOADS, MAX_DOWNLOADS_PER_HOST};
+use crate::utils::{self, download, errors::CoreError};
 use crate::version_manifest::Manifest;
+use tokio::sync::OnceCell;
 
 #[derive(Debug)]
 pub struct Env<'a> {
     profiles: Profiles,
-    manifest: Manifest,
-    launcher_root: &'a Path,
+    /// loaded the first time it is needed, see [`Env::manifest`]
+    manifest: OnceCell<Manifest>,
+    manifest_ttl: Duration,
+    offline: Cell<bool>,
+    dirs: &'a LauncherDirs,
     libs_root: PathBuf,
     assets_root: PathBuf,
+    natives_root: PathBuf,
+    runtimes: Runtimes,
+    scheduler: Scheduler,
 }
 
 impl<'a> Env<'a> {
     pub fn root(&self) -> &Path {
-        self.launcher_root
+        self.dirs.data()
+    }
+
+    pub fn dirs(&self) -> &LauncherDirs {
+        self.dirs
     }
 
     pub fn libs(&self) -> &Path {
@@ -26,61 +49,460 @@ impl<'a> Env<'a> {
         &self.assets_root
     }
 
-    pub async fn fetch_new(launcher_root: &'a Path) -> Self {
-        Self {
+    /// holds the natives of each profile, see [`Profile::natives_path`]
+    pub fn natives(&self) -> &Path {
+        &self.natives_root
+    }
+
+    /// if `offline` is true, or if the version manifest can't be downloaded, the launcher never touches the network
+    pub fn new(dirs: &'a LauncherDirs, offline: bool) -> Result<Self, CoreError<'static>> {
+        let config = Self::launcher_config(dirs)?;
+        download::configure(config.download_options())?;
+
+        let launcher_root = dirs.data();
+        Ok(Self {
             profiles: Profiles::fetch(launcher_root),
-            manifest: Manifest::fetch(launcher_root).await,
+            manifest: OnceCell::new(),
+            manifest_ttl: Duration::from_secs(
+                config.manifest_ttl().copied().unwrap_or_default().into(),
+            ),
+            offline: Cell::new(offline),
             libs_root: launcher_root.join("libs"),
             assets_root: launcher_root.join("assets"),
-            launcher_root,
+            natives_root: launcher_root.join("natives"),
+            runtimes: Runtimes::new(
+                &dirs.cache().join("runtimes"),
+                config
+                    .java_runtimes_url()
+                    .map_or(runtimes::RUNTIMES_URL, String::as_str),
+            ),
+            scheduler: Scheduler::new(
+                config.max_downloads().map_or(MAX_DOWNLOADS, |&max| max) as usize,
+                config
+                    .max_downloads_per_host()
+                    .map_or(MAX_DOWNLOADS_PER_HOST, |&max| max) as usize,
+            ),
+            dirs,
+        })
+    }
+
+    /// returns the version manifest, loading it the first time it is needed
+    /// falls back to the cached manifest and continues offline if it can't be downloaded
+    async fn manifest(&self) -> Result<&Manifest, CoreError<'static>> {
+        let cache_root = self.dirs.cache();
+        self.manifest
+            .get_or_try_init(async || {
+                if self.offline.get() {
+                    return Manifest::cached(cache_root);
+                }
+                match Manifest::fetch(cache_root, self.manifest_ttl).await {
+                    Err(CoreError::DownloadError(err)) => {
+                        self.scheduler.emit(Progress::Offline {
+                            reason: err.to_string(),
+                        });
+                        self.offline.set(true);
+                        Manifest::cached(cache_root)
+                    }
+                    manifest => manifest,
+                }
+            })
+            .await
+    }
+
+    /// whether the launcher only works from the files already on disk
+    pub fn is_offline(&self) -> bool {
+        self.offline.get()
+    }
+
+    /// returns the config shared by every profile, made of the global config and the environment
+    fn launcher_config(dirs: &LauncherDirs) -> Result<Config, CoreError<'static>> {
+        let global = Config::read(&Config::global_config_path(dirs.config()))?;
+        let config = LayeredConfig::default()
+            .with(Layer::Default, Config::defaults())
+            .with(Layer::Global, global.unwrap_or_default())
+            .with(Layer::Env, Config::from_env()?)
+            .resolve();
+        for entry in [
+            "download_timeout",
+            "connect_timeout",
+            "ca_bundles",
+            "max_downloads",
+            "max_downloads_per_host",
+        ] {
+            config.check(entry)?;
         }
+        Ok(config)
+    }
+
+    /// the scheduler downloading the files of installs, see [`Scheduler::subscribe`] to follow their progress
+    pub fn scheduler(&self) -> &Scheduler {
+        &self.scheduler
     }
 
     pub fn profiles(&mut self) -> &Profiles {
         &self.profiles
     }
 
+    /// returns an iterator over all the versions known by the version manifest
+    pub async fn versions(&self) -> Result<impl Iterator<Item = &Version>, CoreError<'static>> {
+        Ok(self.manifest().await?.versions())
+    }
+
     #[inline]
-    fn get_profile(&self, name: &str) -> Option<Profile> {
+    fn get_profile(&self, name: &str) -> Option<Profile<'_>> {
         let profile_metadata = self.profiles.get_named(name)?;
-        let profile = Profile::new(
-            profile_metadata,
-            self.root(),
+        Some(self.profile_from(profile_metadata))
+    }
+
+    #[inline]
+    fn profile_from(&self, metadata: ProfileMetadata) -> Profile<'_> {
+        Profile::new(
+            metadata,
+            self.dirs.config(),
             self.profiles.root(),
             self.libs(),
             self.assets(),
-        );
+            self.natives(),
+            &self.runtimes,
+        )
+    }
+
+    /// re-resolves the floating version of the profile named `name` if it has one
+    /// returns the updated metadata if the profile now resolves to a different version than the one it was last resolved to
+    async fn refresh_alias<'b>(
+        &self,
+        name: &'b str,
+    ) -> Result<Option<ProfileMetadata>, CoreError<'b>> {
+        let mut metadata = self
+            .profiles
+            .get_named(name)
+            .ok_or(CoreError::ProfileNotFound(name))?;
+
+        if !metadata.is_alias() {
+            return Ok(None);
+        }
+
+        let resolved = self
+            .manifest()
+            .await?
+            .resolve(metadata.version())
+            .ok_or(CoreError::MinecraftVersionNotFound)?;
+
+        if resolved == metadata.resolved_version() {
+            return Ok(None);
+        }
+
+        self.scheduler.emit(Progress::AliasResolved {
+            profile: name.to_owned(),
+            alias: metadata.version().to_owned(),
+            version: resolved.to_owned(),
+        });
+        metadata.set_resolved_version(resolved.to_owned());
+        Ok(Some(metadata))
+    }
 
-        Some(profile)
+    /// installs and launches the profile named `name`, see [`Env::install`] and [`Env::launch`]
+    pub async fn execute<'b>(
+        &mut self,
+        name: &'b str,
+        overrides: &[(String, String)],
+        refresh: impl FnOnce(&str) -> bool,
+    ) -> Result<(), CoreError<'b>> {
+        self.install(name, refresh).await?;
+        self.launch(name, overrides)
     }
 
-    pub async fn execute<'b>(&self, name: &'b str) -> Result<(), CoreError<'b>> {
+    /// downloads every file required by the profile named `name` that is missing or corrupted
+    /// the progress of the downloads is reported to the subscriber of [`Env::scheduler`]
+    /// when offline, only checks that every file is already downloaded and keeps floating versions where they are
+    /// if Mojang republished the profile's version since it was installed, `refresh` is given the version id
+    /// and returns whether the profile should be refreshed with the republished client.json
+    pub async fn install<'b>(
+        &mut self,
+        name: &'b str,
+        refresh: impl FnOnce(&str) -> bool,
+    ) -> Result<(), CoreError<'b>> {
+        if !self.is_offline() {
+            // loading the manifest tells whether the launcher has to continue offline
+            self.manifest().await?;
+        }
+        if self.is_offline() {
+            let profile = self
+                .get_profile(name)
+                .ok_or(CoreError::ProfileNotFound(name))?;
+            profile.install_offline()?;
+            return Ok(());
+        }
+
+        if let Some(metadata) = self.refresh_alias(name).await? {
+            let manifest = self.manifest().await?;
+            let mut profile = self.profile_from(metadata.clone());
+            profile.reinit(manifest, &self.scheduler).await?;
+            self.profiles.update(metadata);
+        }
+
+        let manifest = self.manifest().await?;
         let mut profile = self
             .get_profile(name)
             .ok_or(CoreError::ProfileNotFound(name))?;
 
-        profile.install(&self.manifest).await?;
-        profile.execute()?;
+        profile.install(manifest, &self.scheduler, refresh).await?;
+        Ok(())
+    }
+
+    /// launches the installed profile named `name` and waits for the game to exit
+    /// `overrides` are `entry`, `value` pairs which take precedence over the profile's config for this launch only
+    pub fn launch<'b>(
+        &self,
+        name: &'b str,
+        overrides: &[(String, String)],
+    ) -> Result<(), CoreError<'b>> {
+        let overrides = Config::from_pairs(overrides)?;
+        let profile = self
+            .get_profile(name)
+            .ok_or(CoreError::ProfileNotFound(name))?;
+
+        let lock = profile.lock()?.ok_or(CoreError::ProfileRunning(name))?;
+        profile.execute(overrides, &lock)?;
         Ok(())
     }
 
-    pub async fn add(&mut self, name: &str, version: &str) -> Result<(), CoreError<'static>> {
+    /// adds a profile named `name`, which must be a valid directory name, see [`profiles::is_valid_name`]
+    pub async fn add<'b>(&mut self, name: &'b str, version: &str) -> Result<(), CoreError<'b>> {
+        if !profiles::is_valid_name(name) {
+            return Err(CoreError::InvalidProfileName(name));
+        }
         let metadata = ProfileMetadata::new(name.to_owned(), version.to_owned());
         self.profiles.add(metadata);
         Ok(())
     }
 
-    pub fn edit(&mut self, name: &str, entry: &str, value: Option<String>) -> Result<(), ()> {
+    /// returns every file of the profile named `name` that is missing or corrupted
+    pub fn verify<'b>(
+        &self,
+        name: &'b str,
+    ) -> Result<Vec<(RequiredFile, FileStatus)>, CoreError<'b>> {
+        let profile = self
+            .get_profile(name)
+            .ok_or(CoreError::ProfileNotFound(name))?;
+        let client = profile
+            .read_client()
+            .ok_or(CoreError::ProfileNotInstalled(name))?;
+
+        Ok(client::verify_client(
+            self.assets(),
+            self.libs(),
+            &client,
+            profile.dir_path(),
+        ))
+    }
+
+    /// downloads again every file of the profile named `name` that is missing or corrupted, returns the repaired files
+    pub async fn repair<'b>(&self, name: &'b str) -> Result<Vec<RequiredFile>, CoreError<'b>> {
+        let profile = self
+            .get_profile(name)
+            .ok_or(CoreError::ProfileNotFound(name))?;
+        let client = profile
+            .read_client()
+            .ok_or(CoreError::ProfileNotInstalled(name))?;
+
+        if self.is_offline() {
+            let broken =
+                client::verify_client(self.assets(), self.libs(), &client, profile.dir_path());
+            return Err(CoreError::Offline(
+                broken.into_iter().map(|(file, _)| file.path).collect(),
+            ));
+        }
+
+        let repaired = client::repair_client(
+            &self.scheduler,
+            self.assets(),
+            self.libs(),
+            &client,
+            profile.dir_path(),
+        )
+        .await?;
+        Ok(repaired)
+    }
+
+    /// returns the libraries, assets and natives that no profile uses anymore, deleting them unless `dry_run` is true
+    /// the files used by a profile are listed from its client.json, so profiles that aren't installed yet don't keep anything
+    pub fn gc(&self, dry_run: bool) -> Result<Garbage, CoreError<'static>> {
+        let mut referenced = HashSet::new();
+        let mut profile_dirs = HashSet::new();
+        let mut natives_dirs = HashSet::new();
+        for metadata in self.profiles.fetch_profiles() {
+            let profile = self.profile_from(metadata);
+            if let Some(client) = profile.read_client() {
+                referenced.extend(client::shared_files(self.assets(), self.libs(), &client));
+                natives_dirs.insert(profile.natives_dir(&client));
+            }
+            profile_dirs.insert(profile.dir_path().to_path_buf());
+        }
+
+        // the natives of deleted profiles, and of the versions profiles used before, are unused
+        let mut natives = Vec::new();
+        for dir in subdirs(self.natives())? {
+            let unused = subdirs(&dir)?
+                .into_iter()
+                .filter(|dir| !natives_dirs.contains(dir));
+            if natives_dirs.iter().any(|natives| natives.starts_with(&dir)) {
+                natives.extend(unused);
+            } else {
+                natives.push(dir);
+            }
+        }
+        // the directories of profiles deleted without `--purge` are kept, except for the natives they used to hold
+        for dir in subdirs(self.profiles.root())? {
+            if !profile_dirs.contains(&dir) {
+                natives.push(dir.join(".natives"));
+            }
+        }
+
+        let garbage = gc::collect(self.assets(), self.libs(), &natives, &referenced)?;
+        if !dry_run {
+            garbage.remove()?;
+        }
+        Ok(garbage)
+    }
+
+    /// returns every layer of the config of the profile named `name`, see [`Profile::config_layers`]
+    pub fn config_layers<'b>(
+        &self,
+        name: &'b str,
+        overrides: &[(String, String)],
+    ) -> Result<LayeredConfig, CoreError<'b>> {
+        let profile = self
+            .get_profile(name)
+            .ok_or(CoreError::ProfileNotFound(name))?;
+        let overrides = Config::from_pairs(overrides)?;
+        let layers = profile.config_layers(overrides)?;
+        layers.check_layers()?;
+        Ok(layers)
+    }
+
+    /// whether an instance of the profile named `name` is currently running, see [`Profile::is_running`]
+    pub fn is_running<'b>(&self, name: &'b str) -> Result<bool, CoreError<'b>> {
+        let profile = self
+            .get_profile(name)
+            .ok_or(CoreError::ProfileNotFound(name))?;
+        Ok(profile.is_running())
+    }
+
+    /// removes the profile named `name`, if `purge` is true the profile's directory and natives are deleted as well
+    /// refuses to remove a profile that is currently running
+    pub fn remove<'b>(&mut self, name: &'b str, purge: bool) -> Result<(), CoreError<'b>> {
+        let profile = self
+            .get_profile(name)
+            .ok_or(CoreError::ProfileNotFound(name))?;
+
+        if profile.is_running() {
+            return Err(CoreError::ProfileRunning(name));
+        }
+
+        // profiles.json may still list a profile whose name escapes the launcher's directories
+        let dirs = [
+            (
+                profile.dir_path().to_path_buf(),
+                self.profiles.root().to_path_buf(),
+            ),
+            (
+                profile.natives_path().to_path_buf(),
+                self.natives().to_path_buf(),
+            ),
+        ];
+        if purge
+            && dirs
+                .iter()
+                .any(|(dir, root)| dir.exists() && !is_strictly_inside(dir, root))
+        {
+            return Err(CoreError::InvalidProfileName(name));
+        }
+        self.profiles.remove(name);
+
+        if purge {
+            for (dir, _) in dirs {
+                if dir.exists() {
+                    fs::remove_dir_all(dir)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// copies the saves of the profile named `name` into `backups/<name>-<timestamp>`
+    /// returns the path of the backup, or `None` if the profile has no saves
+    pub fn backup_saves<'b>(&self, name: &'b str) -> Result<Option<PathBuf>, CoreError<'b>> {
+        let profile = self
+            .get_profile(name)
+            .ok_or(CoreError::ProfileNotFound(name))?;
+
+        let saves = profile.saves_path();
+        if !saves.exists() {
+            return Ok(None);
+        }
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let backup = self
+            .root()
+            .join("backups")
+            .join(format!("{name}-{timestamp}"));
+
+        utils::copy_dir_all(&saves, &backup)?;
+        Ok(Some(backup))
+    }
+
+    pub fn edit<'b>(
+        &mut self,
+        name: &'b str,
+        entry: &str,
+        value: Option<String>,
+    ) -> Result<(), CoreError<'b>> {
         println!("setting {} entry {} to {:?}", name, entry, value);
-        let mut profile = self.get_profile(name).ok_or(())?;
+        let mut profile = self
+            .get_profile(name)
+            .ok_or(CoreError::ProfileNotFound(name))?;
 
-        // FIXME: that simply is not how it works
-        let mut config = profile.config_mut();
+        let mut updated = profile.read_config()?.unwrap_or_default();
         if let Some(value) = value {
-            config.set(entry, value);
+            updated.set(entry, &value)?;
         } else {
-            config.remove(entry);
+            updated.remove(entry);
         }
+
+        // validates the entry against the config the profile would actually launch with
+        let layers = profile
+            .config_layers(Config::default())?
+            .with(Layer::Profile, updated.clone());
+        layers.check_layers()?;
+        layers.resolve().check(entry)?;
+
+        *profile.config_mut()? = updated;
         Ok(())
     }
 }
+
+/// whether `path` is inside `root` and isn't `root` itself, once symlinks and `..` are resolved
+fn is_strictly_inside(path: &Path, root: &Path) -> bool {
+    match (path.canonicalize(), root.canonicalize()) {
+        (Ok(path), Ok(root)) => path != root && path.starts_with(root),
+        _ => false,
+    }
+}
+
+/// the directories inside `dir`, none if `dir` doesn't exist
+fn subdirs(dir: &Path) -> Result<Vec<PathBuf>, CoreError<'static>> {
+    let mut dirs = Vec::new();
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries {
+            let dir = entry?.path();
+            if dir.is_dir() {
+                dirs.push(dir);
+            }
+        }
+    }
+    Ok(dirs)
+}
@@ -0,0 +1,102 @@
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The files that no profile uses anymore, see [`collect`]
+#[derive(Debug, Default)]
+pub struct Garbage {
+    /// each unused file with the directory it was found in
+    files: Vec<(PathBuf, PathBuf)>,
+    bytes: u64,
+}
+
+impl Garbage {
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.files.iter().map(|(file, _)| file.as_path())
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// the total size of the unused files in bytes
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// adds every file inside `dir` that isn't in `referenced`, `root` is the directory kept by [`Garbage::remove`]
+    fn scan(&mut self, dir: &Path, root: &Path, referenced: &HashSet<PathBuf>) -> io::Result<()> {
+        let mut files = Vec::new();
+        files_in(dir, &mut files)?;
+        for file in files {
+            if referenced.contains(&file) {
+                continue;
+            }
+            self.bytes += fs::symlink_metadata(&file)?.len();
+            self.files.push((file, root.to_path_buf()));
+        }
+        Ok(())
+    }
+
+    /// deletes the unused files, along with the directories they leave empty
+    /// the libraries and assets directories are kept, and so are the directories holding the natives directories
+    pub fn remove(&self) -> io::Result<()> {
+        for (file, root) in &self.files {
+            fs::remove_file(file)?;
+            for dir in file.ancestors().skip(1) {
+                if dir == root || !dir.starts_with(root) || fs::remove_dir(dir).is_err() {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// lists every file inside `dir` recursively, symlinks are listed but not followed
+fn files_in(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    for entry in entries {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            files_in(&entry.path(), files)?;
+        } else {
+            files.push(entry.path());
+        }
+    }
+    Ok(())
+}
+
+/// returns the libraries, asset objects, asset indexes and virtual assets that aren't in `referenced`
+/// along with every file of the natives directories in `natives`, which should only be the ones no profile uses
+pub fn collect(
+    assets_root: &Path,
+    libs_root: &Path,
+    natives: &[PathBuf],
+    referenced: &HashSet<PathBuf>,
+) -> io::Result<Garbage> {
+    let mut garbage = Garbage::default();
+    for dir in [
+        libs_root.to_path_buf(),
+        assets_root.join("objects"),
+        assets_root.join("indexes"),
+        assets_root.join("virtual"),
+    ] {
+        garbage.scan(&dir, &dir, referenced)?;
+    }
+    for dir in natives {
+        let root = dir.parent().unwrap_or(dir);
+        garbage.scan(dir, root, &HashSet::new())?;
+    }
+    Ok(garbage)
+}
@@ -46,6 +46,23 @@ pub struct JavaInstallation {
     pub version: semver::Version,
 }
 
+impl JavaInstallation {
+    /// `version` is the version printed by `java -version`, such as `17.0.9` or `1.8.0_392`
+    pub fn new(path: String, version: &str) -> Option<Self> {
+        let version = Version::parse(&version.replace("_", "+")).ok()?;
+        Some(Self { path, version })
+    }
+
+    /// the release of Java, which Mojang's `majorVersion` refers to
+    /// releases up to Java 8 are numbered `1.x`, so `1.8.0_392` is Java 8
+    pub fn major(&self) -> u64 {
+        match self.version.major {
+            1 => self.version.minor,
+            major => major,
+        }
+    }
+}
+
 /// Finds all java installations on the system
 fn find() -> Vec<String> {
     let search = SearchBuilder::default();
@@ -78,24 +95,23 @@ pub fn list() -> Vec<JavaInstallation> {
     let regex = Regex::new(r#"version "((\d+\.\d+\.\d+)_?(\d+)?)""#).unwrap(); // ^"\d+(\.\d+)*"$
                                                                                //
     for path in paths {
-        let version = Command::new(&path).arg("-version").output().unwrap();
-
-        let version = String::from_utf8(version.stderr).unwrap();
-
-        let captures = regex.captures(&version).unwrap();
-
-        let version = captures[1].replace("_", "+");
-
-        list.push(JavaInstallation {
-            path,
-            version: Version::parse(&version).unwrap(),
-        });
+        // installations whose version can't be read are skipped
+        let Ok(output) = Command::new(&path).arg("-version").output() else {
+            continue;
+        };
+        let output = String::from_utf8_lossy(&output.stderr);
+        let Some(captures) = regex.captures(&output) else {
+            continue;
+        };
+        if let Some(java) = JavaInstallation::new(path, &captures[1]) {
+            list.push(java);
+        }
     }
 
     sort_by_version(&mut list);
-    return list;
+    list
 }
 
-fn sort_by_version(list: &mut Vec<JavaInstallation>) {
+fn sort_by_version(list: &mut [JavaInstallation]) {
     list.sort_by(|a, b| b.version.cmp(&a.version));
 }
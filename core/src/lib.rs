@@ -1,12 +1,16 @@
 use utils::{Arch, OsName};
 
-mod client;
-mod config;
-mod java;
 mod version_manifest;
 
+pub mod client;
+pub mod config;
+pub mod dirs;
 pub mod env;
+pub mod gc;
+pub mod java;
 pub mod profiles;
+pub mod runtimes;
+pub mod scheduler;
 pub mod utils;
 
 pub const OS: OsName = if cfg!(target_os = "windows") {
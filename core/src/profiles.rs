@@ -1,103 +1,201 @@
 use crate::{
     client,
     java::{self},
-    utils::{errors::CoreError, MULTI_PATH_SEPRATOR},
-    version_manifest::Manifest,
+    runtimes::Runtimes,
+    scheduler::{Progress, Scheduler},
+    utils::{
+        download::FileDigest,
+        errors::{ConfigError, CoreError},
+        MULTI_PATH_SEPRATOR,
+    },
+    version_manifest::{self, Manifest},
 };
 use std::{
     borrow::Cow,
-    fs::{self, File, OpenOptions},
+    fs::{self, File, OpenOptions, TryLockError},
     io::{BufReader, Seek, SeekFrom},
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
     process::{Command, Stdio},
 };
 
-use crab_launcher_api::meta::client::Client;
+use crab_launcher_api::meta::{
+    client::{Arguments, Client, Features},
+    utils::Platform,
+};
 use serde::{Deserialize, Serialize};
-use velcro::hash_map_from;
 
-use crate::config::{Config, ConfigMut};
+use crate::config::{Args, Config, ConfigMut, Layer, LayeredConfig};
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct ProfileMetadata {
     name: String,
     version: String,
+    /// the concrete version id the floating `version` was last resolved to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    resolved_version: Option<String>,
 }
 
 impl ProfileMetadata {
     pub fn new(name: String, version: String) -> Self {
-        Self { name, version }
+        Self {
+            name,
+            version,
+            resolved_version: None,
+        }
     }
 
+    /// returns the version of this profile as given by the user, may be a floating alias such as `latest-release`
     pub fn version(&self) -> &str {
         &self.version
     }
 
+    /// returns the concrete version id used by this profile
+    pub fn resolved_version(&self) -> &str {
+        self.resolved_version.as_deref().unwrap_or(&self.version)
+    }
+
+    pub fn is_alias(&self) -> bool {
+        version_manifest::is_alias(&self.version)
+    }
+
+    pub fn set_resolved_version(&mut self, version: String) {
+        self.resolved_version = Some(version);
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
 }
 
+/// whether `name` can name a profile, its directories are named after it so it must be a single plain path component
+pub fn is_valid_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    let single = matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(component)), None) if component == name
+    );
+    single && !name.contains(['/', '\\'])
+}
+
+/// A lock on a profile held by the instance running it, the lock file is deleted once it is dropped
+#[derive(Debug)]
+pub struct ProfileLock {
+    path: PathBuf,
+    _file: File,
+}
+
+impl Drop for ProfileLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Profile<'a> {
     metadata: ProfileMetadata,
     libs_root: &'a Path,
     assets_root: &'a Path,
-    launcher_root: &'a Path,
+    config_root: &'a Path,
+    runtimes: &'a Runtimes,
     root: PathBuf,
+    natives_path: PathBuf,
     client_json_path: PathBuf,
     client_path: PathBuf,
     config_path: PathBuf,
+    lock_path: PathBuf,
 }
 
 impl<'a> Profile<'a> {
     // Creates a new InMemory profile from on disk metadata and a given path to the profile's root directory
     pub fn new(
         metadata: ProfileMetadata,
-        launcher_root: &'a Path,
+        config_root: &'a Path,
         profiles_root: &'a Path,
         libs_root: &'a Path,
         assets_root: &'a Path,
+        natives_root: &Path,
+        runtimes: &'a Runtimes,
     ) -> Self {
         let root = profiles_root.join(metadata.name());
         Self {
+            natives_path: natives_root.join(metadata.name()),
             metadata,
             client_json_path: root.join("client.json"),
             config_path: root.join("config.json"),
             client_path: root.join("client.jar"),
-            launcher_root,
+            lock_path: root.join(".lock"),
+            config_root,
             libs_root,
             assets_root,
+            runtimes,
             root,
         }
     }
 
     /// Initializes the Profile if it isn't already
-    pub async fn init(&mut self, manifest: &Manifest) -> Result<Client, CoreError<'static>> {
+    /// if Mojang republished the version since the profile was initialized, `refresh` is asked whether to reinitialize it
+    pub async fn init(
+        &mut self,
+        manifest: &Manifest,
+        scheduler: &Scheduler,
+        refresh: impl FnOnce(&str) -> bool,
+    ) -> Result<Client, CoreError<'static>> {
         match self.read_client() {
-            Some(client) => Ok(client),
-            None => self.reinit(manifest).await,
+            Some(client)
+                if !self.is_republished(manifest) || !refresh(self.metadata.resolved_version()) =>
+            {
+                Ok(client)
+            }
+            _ => self.reinit(manifest, scheduler).await,
         }
     }
 
-    async fn reinit(&mut self, manifest: &Manifest) -> Result<Client, CoreError<'static>> {
-        let client_raw = manifest.download_version(self.metadata.version()).await?;
+    /// whether the client.json of this profile differs from the one currently published for its version
+    pub fn is_republished(&self, manifest: &Manifest) -> bool {
+        let Some(version) = manifest.version(self.metadata.resolved_version()) else {
+            return false;
+        };
+        FileDigest::of(self.client_json_path())
+            .is_ok_and(|digest| !digest.sha1.eq_ignore_ascii_case(&version.sha1))
+    }
+
+    /// (Re)initializes the Profile by downloading the client.json of its version
+    /// if no installed Java matches the version, the Java runtime it requires is downloaded through `scheduler`
+    pub async fn reinit(
+        &mut self,
+        manifest: &Manifest,
+        scheduler: &Scheduler,
+    ) -> Result<Client, CoreError<'static>> {
+        let client_raw = manifest
+            .download_version(self.metadata.resolved_version())
+            .await?;
         let client: Client =
             serde_json::from_slice(&client_raw).expect("failed to deserialize client.json");
 
         if let Some(ver) = &client.java_version {
-            let manager = java::java_manager();
-            let best_java = manager
+            let best_java = java::java_manager()
                 .list()
                 .iter()
-                .find(|j| j.version.major as u16 == ver.major_version);
-
-            if let Some(java) = best_java {
-                let config = Config::new(hash_map_from! {
-                    "current_java_path": &java.path,
-                });
-
-                self.override_config(config)?;
+                .find(|j| j.major() == u64::from(ver.major_version))
+                .map(|java| PathBuf::from(&java.path));
+
+            let java = match best_java {
+                Some(java) => Some(java),
+                None => match self.runtimes.install(&ver.component, scheduler).await {
+                    Ok(java) => Some(java),
+                    Err(CoreError::JavaRuntimeNotFound(component)) => {
+                        scheduler.emit(Progress::NoRuntime {
+                            component,
+                            major: ver.major_version,
+                        });
+                        None
+                    }
+                    Err(err) => return Err(err),
+                },
+            };
+            if let Some(java) = java {
+                self.config_mut()?
+                    .set("current_java_path", &java.to_string_lossy())?;
             }
         }
         fs::create_dir_all(self.dir_path())?;
@@ -105,10 +203,54 @@ impl<'a> Profile<'a> {
         Ok(client)
     }
 
-    fn dir_path(&self) -> &Path {
+    pub fn dir_path(&self) -> &Path {
         &self.root
     }
 
+    pub fn saves_path(&self) -> PathBuf {
+        self.root.join("saves")
+    }
+
+    /// the directory the natives of this profile are extracted into, outside of its game directory
+    /// it only holds the natives of the installed version, the ones of previous versions are deleted
+    pub fn natives_path(&self) -> &Path {
+        &self.natives_path
+    }
+
+    /// the directory inside [`Profile::natives_path`] holding the natives of `client`
+    pub(crate) fn natives_dir(&self, client: &Client) -> PathBuf {
+        client::natives_dir(
+            client,
+            self.metadata.resolved_version(),
+            self.natives_path(),
+        )
+    }
+
+    /// returns true if an instance of this profile is currently running
+    /// a running instance holds a lock on the profile's lock file until the game exits, see [`Profile::lock`]
+    /// a lock file that can't be opened or that nobody holds is left over from a launcher that didn't exit cleanly
+    pub fn is_running(&self) -> bool {
+        let Ok(file) = File::open(&self.lock_path) else {
+            return false;
+        };
+        matches!(file.try_lock_shared(), Err(TryLockError::WouldBlock))
+    }
+
+    /// locks the profile's lock file until the returned [`ProfileLock`] is dropped
+    /// returns `None` if another instance already holds it
+    pub fn lock(&self) -> Result<Option<ProfileLock>, CoreError<'static>> {
+        fs::create_dir_all(self.dir_path())?;
+        let file = File::create(&self.lock_path)?;
+        match file.try_lock() {
+            Ok(()) => Ok(Some(ProfileLock {
+                path: self.lock_path.clone(),
+                _file: file,
+            })),
+            Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(err)) => Err(err.into()),
+        }
+    }
+
     fn config_path(&self) -> &Path {
         &self.config_path
     }
@@ -122,36 +264,32 @@ impl<'a> Profile<'a> {
     }
 
     /// attempts to read the config.json file for this profile
-    fn read_config(&self) -> Option<Config> {
-        let config_path = self.config_path();
-        let config = fs::read_to_string(&config_path).ok()?;
-        Some(serde_json::from_str(&config).expect("failed to deserialize config.json"))
+    pub fn read_config(&self) -> Result<Option<Config>, CoreError<'static>> {
+        Config::read(self.config_path())
     }
 
-    fn override_config(&mut self, config: Config) -> Result<(), std::io::Error> {
-        let profile_dir = self.dir_path();
-        let config_path = self.config_path();
-        fs::create_dir_all(&profile_dir)?;
-        fs::write(&config_path, serde_json::to_string_pretty(&config)?)?;
-        Ok(())
+    /// returns every layer of the config used by this profile, from the defaults up to the given `overrides`
+    pub fn config_layers(&self, overrides: Config) -> Result<LayeredConfig, CoreError<'static>> {
+        Ok(LayeredConfig::default()
+            .with(Layer::Default, Config::defaults())
+            .with(Layer::Global, Config::read_global(self.config_root)?)
+            .with(Layer::Profile, self.read_config()?.unwrap_or_default())
+            .with(Layer::Env, Config::from_env()?)
+            .with(Layer::Override, overrides))
     }
 
-    /// returns the config used by this profile, and merges it with the global config
-    pub fn get_config(&self) -> Result<Config, std::io::Error> {
-        let global_config = Config::read_global(self.launcher_root)?;
-        if let Some(config) = self.read_config() {
-            Ok(config.merge(global_config))
-        } else {
-            Ok(global_config)
-        }
+    /// returns the effective config used by this profile, see [`Profile::config_layers`]
+    pub fn get_config(&self, overrides: Config) -> Result<Config, CoreError<'static>> {
+        let layers = self.config_layers(overrides)?;
+        layers.check_layers()?;
+        Ok(layers.resolve())
     }
 
-    /// returns a mutable reference to the config used by this profile if any
-    pub fn config_mut(&mut self) -> ConfigMut {
-        let config_path = self.config_path();
-        self.read_config()
-            .unwrap_or(Config::empty())
-            .into_mut(&config_path)
+    /// returns a mutable reference to the config of this profile only, creating it if it doesn't exist
+    pub fn config_mut(&mut self) -> Result<ConfigMut<'_>, CoreError<'static>> {
+        let config = self.read_config()?.unwrap_or_default();
+        fs::create_dir_all(self.dir_path())?;
+        Ok(config.into_mut(self.config_path())?)
     }
 
     pub fn read_client(&self) -> Option<Client> {
@@ -160,10 +298,57 @@ impl<'a> Profile<'a> {
         serde_json::from_str(&data).expect("failed to deserialize client.json")
     }
 
-    pub async fn install(&mut self, manifest: &Manifest) -> Result<(), CoreError<'static>> {
-        let client = self.init(manifest).await?;
-        println!("Downloading profile {}", self.metadata.name());
-        client::install_client(self.assets_root, self.libs_root, client, self.dir_path()).await
+    /// installs this profile, see [`Profile::init`] for `refresh`
+    pub async fn install(
+        &mut self,
+        manifest: &Manifest,
+        scheduler: &Scheduler,
+        refresh: impl FnOnce(&str) -> bool,
+    ) -> Result<(), CoreError<'static>> {
+        let client = self.init(manifest, scheduler, refresh).await?;
+        client::install_client(
+            scheduler,
+            self.assets_root,
+            self.libs_root,
+            &client,
+            self.dir_path(),
+        )
+        .await?;
+        self.extract_natives(&client)
+    }
+
+    /// checks that every file required by this profile is already downloaded, without touching the network
+    /// returns [`CoreError::Offline`] with the files that are missing or corrupted otherwise
+    pub fn install_offline(&self) -> Result<(), CoreError<'static>> {
+        let client = self
+            .read_client()
+            .ok_or_else(|| CoreError::Offline(vec![self.client_json_path().to_path_buf()]))?;
+
+        let broken =
+            client::verify_client(self.assets_root, self.libs_root, &client, self.dir_path());
+        if !broken.is_empty() {
+            return Err(CoreError::Offline(
+                broken.into_iter().map(|(file, _)| file.path).collect(),
+            ));
+        }
+        client::place_assets(self.assets_root, &client, self.dir_path())?;
+        self.extract_natives(&client)
+    }
+
+    /// extracts the natives of `client` into the natives directory of this profile, see [`Profile::natives_path`]
+    fn extract_natives(&self, client: &Client) -> Result<(), CoreError<'static>> {
+        client::extract_natives(
+            self.libs_root,
+            client,
+            self.metadata.resolved_version(),
+            self.natives_path(),
+        )?;
+        // natives used to be extracted into the game directory
+        let legacy = self.dir_path().join(".natives");
+        if legacy.exists() {
+            fs::remove_dir_all(legacy)?;
+        }
+        Ok(())
     }
 
     fn classpath(&self, client: &Client) -> String {
@@ -171,13 +356,8 @@ impl<'a> Profile<'a> {
 
         let mut classpath = Vec::new();
         for lib in libs {
-            if let Some(ref native) = lib.platform_native() {
-                let path = native.sub_path.as_ref().unwrap();
-                let full_path = self.libs_root.join(path);
-                classpath.push(format!("{}", full_path.display()));
-            }
-            if let Some(ref artifact) = lib.downloads.artifact {
-                let path = artifact.sub_path.as_ref().unwrap();
+            for jar in lib.jars(Platform::CURRENT) {
+                let path = jar.sub_path.as_ref().unwrap();
                 let full_path = self.libs_root.join(path);
                 classpath.push(format!("{}", full_path.display()));
             }
@@ -188,35 +368,123 @@ impl<'a> Profile<'a> {
         classpath.join(MULTI_PATH_SEPRATOR)
     }
 
+    /// returns the launcher features enabled by `config`, used to evaluate the rules of the launch arguments
+    fn features(config: &Config) -> Features {
+        let mut features = Features::new();
+        if config.demo().is_some_and(|demo| *demo) {
+            features.enable(Features::IS_DEMO_USER);
+        }
+        if config.resolution_width().is_some() && config.resolution_height().is_some() {
+            features.enable(Features::HAS_CUSTOM_RESOLUTION);
+        }
+
+        let quick_plays = [
+            (
+                config.quick_play_multiplayer(),
+                Features::IS_QUICK_PLAY_MULTIPLAYER,
+            ),
+            (
+                config.quick_play_singleplayer(),
+                Features::IS_QUICK_PLAY_SINGLEPLAYER,
+            ),
+            (config.quick_play_realms(), Features::IS_QUICK_PLAY_REALMS),
+        ];
+        for (_, feature) in quick_plays.iter().filter(|(value, _)| value.is_some()) {
+            features.enable(Features::HAS_QUICK_PLAYS_SUPPORT);
+            features.enable(feature);
+        }
+        features
+    }
+
+    /// the quick play arguments of versions that predate quick play, only joining a server is supported
+    fn legacy_quick_play(config: &Config) -> Result<Vec<String>, CoreError<'static>> {
+        if config.quick_play_singleplayer().is_some() {
+            return Err(CoreError::QuickPlayUnsupported("quick_play_singleplayer"));
+        }
+        if config.quick_play_realms().is_some() {
+            return Err(CoreError::QuickPlayUnsupported("quick_play_realms"));
+        }
+
+        let Some(server) = config.quick_play_multiplayer() else {
+            return Ok(Vec::new());
+        };
+        let (host, port) = match server.rsplit_once(':') {
+            Some((host, port)) if port.parse::<u16>().is_ok() => (host, port),
+            _ => (server.as_str(), "25565"),
+        };
+        Ok(["--server", host, "--port", port]
+            .map(String::from)
+            .to_vec())
+    }
+
     /// generates the java arguments required to launch this profile
     /// NOTE: may panic if [`Self::install`] was not successfully executed first (assumes that the client.json file exists)
     fn generate_arguments(&self, config: &Config) -> Result<Vec<String>, CoreError<'static>> {
         let client = self.read_client().expect("failed to read client.json, Self::generate_arguments must be called after Self::install");
         let classpath = self.classpath(&client);
         let game_dir = self.dir_path();
-        let natives_dir = game_dir.join(".natives");
+        let natives_dir = self.natives_dir(&client);
+        let game_assets = client::game_assets_dir(self.assets_root, &client, game_dir);
+        let quick_play_path = game_dir.join("quickPlay").join("log.json");
 
         let raw_args = client.arguments;
-        let (mut jvm_args, mut game_args) = raw_args.into_raw();
+        let features = Self::features(config);
+        let is_legacy = matches!(raw_args, Arguments::MinecraftArgs(_));
+        let supports_quick_play = raw_args.supports_feature(Features::IS_QUICK_PLAY_MULTIPLAYER);
+        let (mut jvm_args, mut game_args) = raw_args.into_raw(&features);
+
+        // legacy versions have no rules for these so they have to be added manually
+        if is_legacy && features.is_enabled(Features::HAS_CUSTOM_RESOLUTION) {
+            game_args.extend(
+                [
+                    "--width",
+                    "${resolution_width}",
+                    "--height",
+                    "${resolution_height}",
+                ]
+                .map(String::from),
+            );
+        }
+        if !supports_quick_play {
+            game_args.extend(Self::legacy_quick_play(config)?);
+        }
+        // no version has a rule for fullscreen, but they all support the argument
+        if config.fullscreen().is_some_and(|fullscreen| *fullscreen) {
+            game_args.push("--fullscreen".to_string());
+        }
+        // extra arguments come last so they can override the ones required by the game
+        if let Some(extra) = config.extra_jvm_args() {
+            jvm_args.extend_from_slice(extra.as_slice());
+        }
+        if let Some(extra) = config.extra_game_args() {
+            game_args.extend_from_slice(extra.as_slice());
+        }
         let regex = regex::Regex::new(r"\$\{(\w+)\}")
             .expect("failed to compile regex for parsing arguments");
 
         let fmt_arg = |arg: &str| {
             Some(match arg {
-                "game_directory" => game_dir.to_str().unwrap(),
-                "assets_root" | "game_assets" => self.assets_root.to_str().unwrap(),
-                "assets_index_name" => &client.assets,
-                "version_name" => self.metadata.version(),
-                "classpath" => classpath.as_str(),
-                "natives_directory" => natives_dir.to_str().unwrap(),
-                "auth_uuid" => "e371151a-b6b4-496a-b446-0abcd3e75ec4",
-                _ => config.get(arg)?,
+                "game_directory" => Cow::Borrowed(game_dir.to_str().unwrap()),
+                "assets_root" => Cow::Borrowed(self.assets_root.to_str().unwrap()),
+                "game_assets" => Cow::Borrowed(game_assets.to_str().unwrap()),
+                "assets_index_name" => Cow::Borrowed(client.assets.as_str()),
+                "version_name" => Cow::Borrowed(self.metadata.resolved_version()),
+                "classpath" => Cow::Borrowed(classpath.as_str()),
+                "natives_directory" => Cow::Borrowed(natives_dir.to_str().unwrap()),
+                "auth_uuid" => Cow::Borrowed("e371151a-b6b4-496a-b446-0abcd3e75ec4"),
+                "quickPlayPath" => Cow::Borrowed(quick_play_path.to_str().unwrap()),
+                "quickPlayMultiplayer" => Cow::Borrowed(config.quick_play_multiplayer()?.as_str()),
+                "quickPlaySingleplayer" => {
+                    Cow::Borrowed(config.quick_play_singleplayer()?.as_str())
+                }
+                "quickPlayRealms" => Cow::Borrowed(config.quick_play_realms()?.as_str()),
+                _ => Cow::Owned(config.get(arg)?),
             })
         };
 
         let fmt_args = |args: &mut Vec<String>| {
             for arg in args {
-                let new_value = regex.replace_all(&arg, |caps: &regex::Captures| {
+                let new_value = regex.replace_all(arg, |caps: &regex::Captures| {
                     let fmt_spec = caps.get(1).unwrap().as_str();
                     fmt_arg(fmt_spec).unwrap_or_default()
                 });
@@ -234,29 +502,93 @@ impl<'a> Profile<'a> {
         Ok([jvm_args, game_args].concat())
     }
 
-    pub fn execute(&self) -> Result<(), CoreError<'static>> {
-        let config = self.get_config()?;
-        let current_java_path = config.get("current_java_path").unwrap();
-        let max_ram = config.get("max_ram").unwrap();
-        let min_ram = config.get("min_ram").unwrap();
+    /// launches this profile, `overrides` take precedence over every other config layer
+    /// `_lock` is this profile's lock, held for as long as the game and its hooks run, see [`Profile::lock`]
+    pub fn execute(
+        &self,
+        overrides: Config,
+        _lock: &ProfileLock,
+    ) -> Result<(), CoreError<'static>> {
+        let config = self.get_config(overrides)?;
+        config.validate()?;
+        let current_java_path = config
+            .current_java_path()
+            .ok_or(ConfigError::Missing("current_java_path"))?;
+        let max_ram = config.max_ram().ok_or(ConfigError::Missing("max_ram"))?;
+        let min_ram = config.min_ram().ok_or(ConfigError::Missing("min_ram"))?;
 
         let args = self.generate_arguments(&config)?;
 
+        if let Some(hook) = config.pre_launch() {
+            self.run_hook("pre_launch", hook, &config, None)?;
+        }
+
         dbg!("executing with args: {:?}", &args);
-        // TODO: make use of client.arguments
-        let output = Command::new(current_java_path)
+        let mut command = match config.wrapper_command().map(Args::as_slice) {
+            Some([wrapper, wrapper_args @ ..]) => {
+                let mut command = Command::new(wrapper);
+                command.args(wrapper_args).arg(current_java_path);
+                command
+            }
+            _ => Command::new(current_java_path),
+        };
+
+        if let Some(env) = config.env() {
+            command.envs(env.iter());
+        }
+
+        let mut child = command
             .arg(format!("-Xmx{}M", max_ram))
             .arg(format!("-Xms{}M", min_ram))
             .args(args)
             .stdin(Stdio::inherit())
             .stdout(Stdio::inherit())
             .stderr(Stdio::inherit())
-            .output()?;
+            .spawn()?;
+
+        let status = child.wait()?;
+        // the game was killed by a signal if there is no exit code
+        let exit_code = status.code().unwrap_or(-1);
 
-        if !output.status.success() {
-            return Err(CoreError::MinecraftFailure(output.status.code().unwrap()));
+        let post_exit = match config.post_exit() {
+            Some(hook) => self.run_hook("post_exit", hook, &config, Some(exit_code)),
+            None => Ok(()),
+        };
+
+        if !status.success() {
+            return Err(CoreError::MinecraftFailure(exit_code));
         }
 
+        post_exit
+    }
+
+    /// runs the shell command `hook`, exposing the profile name, game directory and exit code of the game if any as
+    /// `CRAB_PROFILE`, `CRAB_GAME_DIR` and `CRAB_EXIT_CODE`
+    fn run_hook(
+        &self,
+        name: &'static str,
+        hook: &str,
+        config: &Config,
+        exit_code: Option<i32>,
+    ) -> Result<(), CoreError<'static>> {
+        let mut command = Command::new("sh");
+        command
+            .arg("-c")
+            .arg(hook)
+            .env("CRAB_PROFILE", self.metadata.name())
+            .env("CRAB_GAME_DIR", self.dir_path());
+
+        if let Some(env) = config.env() {
+            command.envs(env.iter());
+        }
+        if let Some(exit_code) = exit_code {
+            command.env("CRAB_EXIT_CODE", exit_code.to_string());
+        }
+
+        let status = command.status()?;
+        if !status.success() {
+            return Err(CoreError::HookFailure(name, status.code().unwrap_or(-1)));
+        }
         Ok(())
     }
 }
@@ -273,7 +605,9 @@ impl Profiles {
     }
 
     pub fn fetch_profiles(&self) -> Vec<ProfileMetadata> {
-        let reader = BufReader::new(&self.fd);
+        let mut fd = &self.fd;
+        fd.seek(SeekFrom::Start(0)).unwrap();
+        let reader = BufReader::new(fd);
         serde_json::from_reader(reader).unwrap_or_default()
     }
 
@@ -307,4 +641,22 @@ impl Profiles {
         profiles.push(profile);
         self.write_profiles(&profiles);
     }
+
+    /// replaces the metadata of the profile with the same name as `profile`
+    pub fn update(&mut self, profile: ProfileMetadata) {
+        let mut profiles = self.fetch_profiles();
+        if let Some(entry) = profiles.iter_mut().find(|x| x.name == profile.name) {
+            *entry = profile;
+        }
+        self.write_profiles(&profiles);
+    }
+
+    /// removes the profile named `name` from profiles.json, returns the removed profile's metadata if any
+    pub fn remove(&mut self, name: &str) -> Option<ProfileMetadata> {
+        let mut profiles = self.fetch_profiles();
+        let index = profiles.iter().position(|x| x.name == name)?;
+        let removed = profiles.remove(index);
+        self.write_profiles(&profiles);
+        Some(removed)
+    }
 }
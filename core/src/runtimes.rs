@@ -0,0 +1,211 @@
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+#[cfg(unix)]
+use std::{
+    io,
+    os::unix::fs::{symlink, PermissionsExt},
+};
+
+use crab_launcher_api::meta::{
+    runtime::{self, RuntimeFile, RuntimeIndex, RuntimeManifest},
+    utils::{OsName, Platform},
+};
+
+use crate::{
+    client::RequiredFile,
+    scheduler::{Progress, Scheduler},
+    utils::{
+        download::{self, DownloadError, FileDigest},
+        errors::CoreError,
+    },
+};
+
+/// the index of the Java runtimes published by Mojang for every platform
+pub const RUNTIMES_URL: &str = "https://piston-meta.mojang.com/v1/products/java-runtime/2ec0cc96c44e5a76b9c8b7c39df7210883d12871/all.json";
+
+/// Downloads the Java runtimes required by versions into `<root>/<component>`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Runtimes {
+    root: PathBuf,
+    index_url: String,
+}
+
+impl Runtimes {
+    /// `index_url` is the url of the runtime index, usually [`RUNTIMES_URL`]
+    pub fn new(root: &Path, index_url: &str) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            index_url: index_url.to_string(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn dir(&self, component: &str) -> PathBuf {
+        self.root.join(component)
+    }
+
+    /// the java executable of the runtime `component`
+    pub fn java_path(&self, component: &str) -> PathBuf {
+        let dir = self.dir(component);
+        match Platform::CURRENT.os {
+            OsName::Osx => dir.join("jre.bundle/Contents/Home/bin/java"),
+            OsName::Windows => dir.join("bin").join("javaw.exe"),
+            OsName::Linux => dir.join("bin").join("java"),
+        }
+    }
+
+    /// stores the SHA-1 of the manifest of the installed runtime, so an up to date runtime isn't verified again
+    fn marker_path(&self, component: &str) -> PathBuf {
+        self.root.join(format!("{component}.sha1"))
+    }
+
+    /// downloads the runtime `component` for the current platform through `scheduler` if it isn't installed yet
+    /// returns the path of its java executable
+    pub async fn install(
+        &self,
+        component: &str,
+        scheduler: &Scheduler,
+    ) -> Result<PathBuf, CoreError<'static>> {
+        let data = download::get(&self.index_url).await?;
+        let index: RuntimeIndex = serde_json::from_slice(&data)
+            .map_err(|_| DownloadError::Corrupted(self.index_url.clone()))?;
+        let runtime = index
+            .get(runtime::platform_key(Platform::CURRENT))
+            .and_then(|components| components.get(component))
+            .and_then(|releases| releases.first())
+            .ok_or_else(|| CoreError::JavaRuntimeNotFound(component.to_string()))?;
+
+        let java = self.java_path(component);
+        let marker = self.marker_path(component);
+        let manifest_sha1 = &runtime.manifest.sha1;
+        if java.exists()
+            && fs::read_to_string(&marker).is_ok_and(|sha1| sha1.trim() == manifest_sha1)
+        {
+            return Ok(java);
+        }
+
+        let data = download::get(&runtime.manifest.url).await?;
+        let expected = FileDigest {
+            size: runtime.manifest.size as u64,
+            sha1: manifest_sha1.clone(),
+        };
+        if !FileDigest::of_bytes(&data).matches(&expected) {
+            return Err(DownloadError::Corrupted(runtime.manifest.url.clone()).into());
+        }
+        let manifest: RuntimeManifest = serde_json::from_slice(&data)
+            .map_err(|_| DownloadError::Corrupted(runtime.manifest.url.clone()))?;
+
+        scheduler.emit(Progress::InstallingRuntime {
+            component: component.to_string(),
+            version: runtime.version.name.clone(),
+        });
+        let dir = self.dir(component);
+        place_runtime(&dir, &manifest, scheduler).await?;
+        fs::write(marker, manifest_sha1)?;
+        Ok(java)
+    }
+}
+
+/// the path of the runtime file called `name` inside `dir`, `None` if the name would escape `dir`
+fn runtime_path(dir: &Path, name: &str) -> Option<PathBuf> {
+    let name = Path::new(name);
+    name.components()
+        .all(|component| matches!(component, Component::Normal(_)))
+        .then(|| dir.join(name))
+}
+
+/// whether the symlink at `link` pointing to the relative `target` stays inside `dir`
+#[cfg(unix)]
+fn link_stays_inside(dir: &Path, link: &Path, target: &str) -> bool {
+    let Ok(mut depth) = link
+        .parent()
+        .unwrap_or(link)
+        .strip_prefix(dir)
+        .map(|parent| parent.components().count())
+    else {
+        return false;
+    };
+    for component in Path::new(target).components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir if depth > 0 => depth -= 1,
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// creates the directories, files and symlinks listed by `manifest` in `dir`
+/// symlinks are only created once every file is downloaded, and only if they point inside `dir`, see [`link_and_mark_executables`]
+async fn place_runtime(
+    dir: &Path,
+    manifest: &RuntimeManifest,
+    scheduler: &Scheduler,
+) -> Result<(), CoreError<'static>> {
+    let entries: Vec<_> = manifest
+        .files
+        .iter()
+        .filter_map(|(name, file)| Some((runtime_path(dir, name)?, file)))
+        .collect();
+
+    for (path, file) in &entries {
+        if let RuntimeFile::Directory = file {
+            fs::create_dir_all(path)?;
+        }
+    }
+
+    let files = entries
+        .iter()
+        .filter_map(|(path, file)| match file {
+            RuntimeFile::File { downloads, .. } => Some(RequiredFile {
+                path: path.clone(),
+                url: downloads.raw.url.clone(),
+                expected: FileDigest {
+                    size: downloads.raw.size as u64,
+                    sha1: downloads.raw.sha1.clone(),
+                },
+            }),
+            _ => None,
+        })
+        .collect();
+    scheduler.run(files).await?;
+
+    #[cfg(unix)]
+    link_and_mark_executables(dir, &entries)?;
+    Ok(())
+}
+
+/// creates the symlinks of `entries` that point inside `dir` and makes their executables executable
+/// the runtimes Mojang provides for Windows have neither
+#[cfg(unix)]
+fn link_and_mark_executables(dir: &Path, entries: &[(PathBuf, &RuntimeFile)]) -> io::Result<()> {
+    for (path, file) in entries {
+        match file {
+            RuntimeFile::File {
+                executable: true, ..
+            } => {
+                fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+            }
+            RuntimeFile::Link { target } if link_stays_inside(dir, path, target) => {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                match fs::symlink_metadata(path) {
+                    Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(path)?,
+                    Ok(_) => fs::remove_file(path)?,
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                    Err(err) => return Err(err),
+                }
+                symlink(target, path)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,188 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use futures::{
+    channel::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    stream::FuturesUnordered,
+    StreamExt,
+};
+use tokio::sync::Semaphore;
+
+use crate::{
+    client::{FileStatus, RequiredFile},
+    utils::download::{self, DownloadError},
+};
+
+/// An event reported while the files of an install are downloaded
+/// along with the steps of the install the user should know about, such as installing a Java runtime
+#[derive(Debug, Clone, PartialEq)]
+pub enum Progress {
+    /// the file was added to the queue, `size` is its expected size in bytes
+    Queued { path: PathBuf, size: u64 },
+    /// the download of the file started
+    Started { path: PathBuf },
+    /// `bytes` more bytes of the file were written to disk
+    Bytes { path: PathBuf, bytes: u64 },
+    /// the file is on disk and matches its expected size and SHA-1, whether it was downloaded or already there
+    Verified { path: PathBuf },
+    /// the file couldn't be downloaded
+    Failed { path: PathBuf, error: String },
+    /// the Java runtime `component` is being installed, `version` is the release of Java it provides
+    InstallingRuntime { component: String, version: String },
+    /// no installed Java is the `major` release the version requires and Mojang doesn't provide `component` for this platform
+    /// the profile keeps using its current Java
+    NoRuntime { component: String, major: u16 },
+    /// the floating version `alias` of `profile` now resolves to `version`, the profile is updated to it
+    AliasResolved {
+        profile: String,
+        alias: String,
+        version: String,
+    },
+    /// the version manifest couldn't be downloaded, the launcher continues offline from the cached one
+    Offline { reason: String },
+}
+
+/// how many files are downloaded at once by default, see the `max_downloads` config entry
+pub const MAX_DOWNLOADS: u32 = 16;
+/// how many files are downloaded at once from the same host by default, see the `max_downloads_per_host` config entry
+pub const MAX_DOWNLOADS_PER_HOST: u32 = 8;
+
+/// Downloads the files of installs, limiting how many downloads run at once overall and per host
+#[derive(Debug)]
+pub struct Scheduler {
+    max_downloads: usize,
+    max_per_host: usize,
+    events: Mutex<Option<UnboundedSender<Progress>>>,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new(MAX_DOWNLOADS as usize, MAX_DOWNLOADS_PER_HOST as usize)
+    }
+}
+
+impl Scheduler {
+    pub fn new(max_downloads: usize, max_per_host: usize) -> Self {
+        Self {
+            max_downloads,
+            max_per_host,
+            events: Mutex::new(None),
+        }
+    }
+
+    /// returns a stream of the [`Progress`] events of every following download, replacing any previous subscriber
+    pub fn subscribe(&self) -> UnboundedReceiver<Progress> {
+        let (sender, receiver) = mpsc::unbounded();
+        *self.events.lock().unwrap() = Some(sender);
+        receiver
+    }
+
+    /// stops sending events, ending the stream returned by [`Scheduler::subscribe`]
+    pub fn unsubscribe(&self) {
+        *self.events.lock().unwrap() = None;
+    }
+
+    pub(crate) fn emit(&self, event: Progress) {
+        if let Some(ref events) = *self.events.lock().unwrap() {
+            // the subscriber may have stopped listening, which is fine
+            let _ = events.unbounded_send(event);
+        }
+    }
+
+    /// downloads every file of `files` that is missing or corrupted
+    /// files already on disk, such as the libraries and assets shared with other profiles, are only verified
+    /// and a file queued more than once is only downloaded once
+    /// every file is attempted before returning the first error
+    pub async fn run(&self, files: Vec<RequiredFile>) -> Result<(), DownloadError> {
+        let mut queued = HashSet::new();
+        let files: Vec<_> = files
+            .into_iter()
+            .filter(|file| queued.insert(file.path.clone()))
+            .collect();
+        for file in &files {
+            self.emit(Progress::Queued {
+                path: file.path.clone(),
+                size: file.expected.size,
+            });
+        }
+
+        let slots = Semaphore::new(self.max_downloads);
+        let mut hosts: HashMap<&str, Arc<Semaphore>> = HashMap::new();
+        let mut downloads: FuturesUnordered<_> = files
+            .iter()
+            .map(|file| {
+                let host = hosts
+                    .entry(host_of(&file.url))
+                    .or_insert_with(|| Arc::new(Semaphore::new(self.max_per_host)))
+                    .clone();
+                self.download(file, &slots, host)
+            })
+            .collect();
+
+        let mut result = Ok(());
+        while let Some(output) = downloads.next().await {
+            if let Err(err) = output {
+                result = result.and(Err(err));
+            }
+        }
+        result
+    }
+
+    async fn download(
+        &self,
+        file: &RequiredFile,
+        slots: &Semaphore,
+        host: Arc<Semaphore>,
+    ) -> Result<(), DownloadError> {
+        let path = || file.path.clone();
+        let status = {
+            // hashing takes a global slot too, so verifying a full asset set doesn't queue a blocking task per file
+            let _slot = slots.acquire().await.expect("semaphore closed");
+            let file = file.clone();
+            tokio::task::spawn_blocking(move || file.status())
+                .await
+                .expect("failed to verify file")
+        };
+        if status == FileStatus::Valid {
+            self.emit(Progress::Verified { path: path() });
+            return Ok(());
+        }
+
+        // the host's slot is taken first so waiting on a busy host doesn't hold a global slot
+        let _host = host.acquire().await.expect("semaphore closed");
+        let _slot = slots.acquire().await.expect("semaphore closed");
+        self.emit(Progress::Started { path: path() });
+
+        let on_bytes = |bytes| {
+            self.emit(Progress::Bytes {
+                path: path(),
+                bytes,
+            })
+        };
+        match download::downloader()
+            .download_verified(&file.url, &file.path, &file.expected, on_bytes)
+            .await
+        {
+            Ok(()) => {
+                self.emit(Progress::Verified { path: path() });
+                Ok(())
+            }
+            Err(err) => {
+                self.emit(Progress::Failed {
+                    path: path(),
+                    error: err.to_string(),
+                });
+                Err(err)
+            }
+        }
+    }
+}
+
+/// the host part of `url`, or the whole url if it has none
+fn host_of(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.split(['/', '?', '#']).next().unwrap_or(rest)
+}
@@ -1,4 +1,22 @@
+use std::{
+    collections::hash_map::RandomState,
+    fmt::Display,
+    fs::{self, File, OpenOptions},
+    hash::{BuildHasher, Hasher},
+    io::{self, ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::Duration,
+};
+
 use bytes::Bytes;
+use reqwest::{
+    header::{self, HeaderValue},
+    Certificate, NoProxy, Proxy, Response, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+
 #[allow(dead_code)]
 #[derive(Debug)]
 pub enum DownloadError {
@@ -7,6 +25,54 @@ pub enum DownloadError {
     Other(reqwest::Error),
     Status(reqwest::StatusCode),
     Io(std::io::Error),
+    /// the file downloaded from the given url doesn't match its expected size or SHA-1
+    Corrupted(String),
+    /// the HTTP client couldn't be built from the download settings, for the given reason
+    Settings(String),
+}
+
+impl DownloadError {
+    /// whether the download may succeed if it is attempted again
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            DownloadError::Timeout => true,
+            // a stale part file is removed before returning 416, so the next attempt starts over
+            DownloadError::Status(status) => {
+                status.is_server_error()
+                    || *status == StatusCode::TOO_MANY_REQUESTS
+                    || *status == StatusCode::RANGE_NOT_SATISFIABLE
+            }
+            DownloadError::Other(err) => {
+                err.is_connect() || err.is_request() || err.is_body() || err.is_decode()
+            }
+            DownloadError::Io(err) => matches!(
+                err.kind(),
+                ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::UnexpectedEof
+                    | ErrorKind::TimedOut
+            ),
+            DownloadError::InvaildUrl
+            | DownloadError::Corrupted(_)
+            | DownloadError::Settings(_) => false,
+        }
+    }
+}
+
+impl Display for DownloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DownloadError::InvaildUrl => write!(f, "invalid url"),
+            DownloadError::Timeout => write!(f, "timed out"),
+            DownloadError::Other(err) => write!(f, "{err}"),
+            DownloadError::Status(status) => write!(f, "the server responded with {status}"),
+            DownloadError::Io(err) => write!(f, "{err}"),
+            DownloadError::Corrupted(url) => {
+                write!(f, "the file downloaded from {url} is corrupted")
+            }
+            DownloadError::Settings(reason) => write!(f, "{reason}"),
+        }
+    }
 }
 
 impl From<reqwest::Error> for DownloadError {
@@ -29,13 +95,395 @@ impl From<std::io::Error> for DownloadError {
     }
 }
 
+/// The size and SHA-1 of a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDigest {
+    pub size: u64,
+    pub sha1: String,
+}
+
+impl FileDigest {
+    /// computes the digest of the file at `path`
+    pub fn of(path: &Path) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let mut hasher = Sha1::new();
+        let size = io::copy(&mut file, &mut hasher)?;
+        Ok(Self {
+            size,
+            sha1: hex(&hasher.finalize()),
+        })
+    }
+
+    /// computes the digest of `data`
+    pub fn of_bytes(data: &[u8]) -> Self {
+        Self {
+            size: data.len() as u64,
+            sha1: hex(&Sha1::digest(data)),
+        }
+    }
+
+    /// whether both digests describe the same file, SHA-1s are compared ignoring case
+    pub fn matches(&self, other: &FileDigest) -> bool {
+        self.size == other.size && self.sha1.eq_ignore_ascii_case(&other.sha1)
+    }
+}
+
+/// encodes `bytes` as a lowercase hex string
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// The validators of a cached response, sent back to only download it again if it changed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl Validators {
+    fn of(response: &Response) -> Self {
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value: &HeaderValue| value.to_str().ok())
+                .map(str::to_string)
+        };
+        Self {
+            etag: header(header::ETAG),
+            last_modified: header(header::LAST_MODIFIED),
+        }
+    }
+}
+
+/// The response to a conditional request, see [`Downloader::get_if_modified`]
+#[derive(Debug)]
+pub enum Conditional {
+    /// the cached response is still up to date
+    NotModified,
+    Modified(Bytes, Validators),
+}
+
+/// How the launcher connects to download servers, and how failed downloads are retried
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    /// the number of times a failed download is attempted again
+    pub retries: u32,
+    /// the delay before the first retry, doubled after each retry
+    pub backoff: Duration,
+    /// the time allowed to connect to a server
+    pub connect_timeout: Duration,
+    /// the time allowed between two reads of the response
+    pub read_timeout: Duration,
+    pub user_agent: String,
+    /// the proxy used for `http` urls, the system's proxy settings are used if neither proxy is set
+    pub http_proxy: Option<String>,
+    /// the proxy used for `https` urls
+    pub https_proxy: Option<String>,
+    /// a comma separated list of the hosts that are reached without the proxies
+    pub no_proxy: Option<String>,
+    /// PEM files with certificates trusted on top of the system's
+    pub ca_bundles: Vec<PathBuf>,
+    /// url prefixes with the mirrors they are rewritten to, in the order they are tried
+    pub mirrors: Vec<(String, Vec<String>)>,
+}
+
+impl DownloadOptions {
+    /// returns the urls `url` is downloaded from, in the order they are tried
+    /// the mirrors of the longest prefix of `url` are used, `url` itself is only used if no prefix matches
+    /// or if the matching prefix has no mirrors
+    pub fn rewrite(&self, url: &str) -> Vec<String> {
+        let matching = self
+            .mirrors
+            .iter()
+            .filter(|(prefix, _)| url.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len());
+
+        match matching {
+            Some((prefix, mirrors)) if !mirrors.is_empty() => mirrors
+                .iter()
+                .map(|mirror| format!("{mirror}{}", &url[prefix.len()..]))
+                .collect(),
+            _ => vec![url.to_string()],
+        }
+    }
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            retries: 5,
+            backoff: Duration::from_millis(500),
+            connect_timeout: Duration::from_secs(10),
+            read_timeout: Duration::from_secs(30),
+            user_agent: format!("CrabLauncher/{}", env!("CARGO_PKG_VERSION")),
+            http_proxy: None,
+            https_proxy: None,
+            no_proxy: None,
+            ca_bundles: Vec::new(),
+            mirrors: Vec::new(),
+        }
+    }
+}
+
+/// Downloads files, retrying transient failures with a jittered exponential backoff
+/// a single HTTP client is shared by every download so connections are reused
+#[derive(Debug)]
+pub struct Downloader {
+    client: reqwest::Client,
+    options: DownloadOptions,
+}
+
+impl Downloader {
+    pub fn new(options: DownloadOptions) -> Result<Self, DownloadError> {
+        let settings = |what: &str, err: &dyn std::fmt::Display| {
+            DownloadError::Settings(format!("{what}: {err}"))
+        };
+
+        let mut builder = reqwest::Client::builder()
+            .connect_timeout(options.connect_timeout)
+            .read_timeout(options.read_timeout)
+            .user_agent(&options.user_agent);
+
+        let no_proxy = options.no_proxy.as_deref().and_then(NoProxy::from_string);
+        if let Some(ref url) = options.http_proxy {
+            let proxy = Proxy::http(url.as_str()).map_err(|err| settings(url, &err))?;
+            builder = builder.proxy(proxy.no_proxy(no_proxy.clone()));
+        }
+        if let Some(ref url) = options.https_proxy {
+            let proxy = Proxy::https(url.as_str()).map_err(|err| settings(url, &err))?;
+            builder = builder.proxy(proxy.no_proxy(no_proxy));
+        }
+
+        for path in &options.ca_bundles {
+            let name = path.display().to_string();
+            let pem = fs::read(path).map_err(|err| settings(&name, &err))?;
+            let certificates =
+                Certificate::from_pem_bundle(&pem).map_err(|err| settings(&name, &err))?;
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        let client = builder
+            .build()
+            .map_err(|err| settings("HTTP client", &err))?;
+        Ok(Self { client, options })
+    }
+
+    /// Downloads a file from a given url and returns it as a byte vector
+    pub async fn get(&self, url: &str) -> Result<Bytes, DownloadError> {
+        self.with_mirrors(url, async |url| {
+            self.retry(async || self.get_once(url).await).await
+        })
+        .await
+    }
+
+    /// Downloads a file from a given url to `path`
+    /// the file is downloaded to a `.part` file next to `path` first, see [`Downloader::download_part`], which is then renamed to `path`
+    pub async fn download_to(&self, url: &str, path: &Path) -> Result<(), DownloadError> {
+        let part = part_path(path);
+        self.download_part(url, &part, |_| {}).await?;
+        fs::rename(part, path)?;
+        Ok(())
+    }
+
+    /// [`Downloader::download_to`] but the file is rejected if its size or SHA-1 don't match `expected`
+    /// `path` is left untouched if the file is rejected, `on_bytes` is called as in [`Downloader::download_part`]
+    pub async fn download_verified(
+        &self,
+        url: &str,
+        path: &Path,
+        expected: &FileDigest,
+        on_bytes: impl Fn(u64),
+    ) -> Result<(), DownloadError> {
+        let part = part_path(path);
+        let digest = self.download_part(url, &part, on_bytes).await?;
+        if !digest.matches(expected) {
+            fs::remove_file(&part)?;
+            return Err(DownloadError::Corrupted(url.to_string()));
+        }
+        fs::rename(part, path)?;
+        Ok(())
+    }
+
+    /// Downloads a file from a given url to the `.part` file `part` and returns the digest of the whole file
+    /// if `part` already exists the download is resumed from its end
+    /// `on_bytes` is called with the number of bytes of each chunk written
+    pub async fn download_part(
+        &self,
+        url: &str,
+        part: &Path,
+        on_bytes: impl Fn(u64),
+    ) -> Result<FileDigest, DownloadError> {
+        if let Some(parent) = part.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // a download interrupted on one mirror is resumed from the next one
+        self.with_mirrors(url, async |url| {
+            self.retry(async || self.resume_once(url, part, &on_bytes).await)
+                .await
+        })
+        .await
+    }
+
+    /// runs `download` on each url `url` is rewritten to until one succeeds, see [`DownloadOptions::rewrite`]
+    /// returns the error of the last url if they all fail
+    async fn with_mirrors<T>(
+        &self,
+        url: &str,
+        download: impl AsyncFn(&str) -> Result<T, DownloadError>,
+    ) -> Result<T, DownloadError> {
+        let mut result = Err(DownloadError::InvaildUrl);
+        for url in self.options.rewrite(url) {
+            result = download(&url).await;
+            if result.is_ok() {
+                break;
+            }
+        }
+        result
+    }
+
+    /// [`Downloader::get`] but the file is only downloaded if it changed since the response `validators` come from
+    pub async fn get_if_modified(
+        &self,
+        url: &str,
+        validators: &Validators,
+    ) -> Result<Conditional, DownloadError> {
+        self.with_mirrors(url, async |url| {
+            self.retry(async || self.get_if_modified_once(url, validators).await)
+                .await
+        })
+        .await
+    }
+
+    async fn get_if_modified_once(
+        &self,
+        url: &str,
+        validators: &Validators,
+    ) -> Result<Conditional, DownloadError> {
+        let mut request = self.client.get(url);
+        if let Some(ref etag) = validators.etag {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+        if let Some(ref last_modified) = validators.last_modified {
+            request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+        }
+
+        let response = request.send().await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(Conditional::NotModified);
+        }
+        if !response.status().is_success() {
+            return Err(DownloadError::Status(response.status()));
+        }
+        let validators = Validators::of(&response);
+        Ok(Conditional::Modified(response.bytes().await?, validators))
+    }
+
+    async fn get_once(&self, url: &str) -> Result<Bytes, DownloadError> {
+        let response = self.client.get(url).send().await?;
+        if !response.status().is_success() {
+            return Err(DownloadError::Status(response.status()));
+        }
+        let bytes = response.bytes().await?;
+        Ok(bytes)
+    }
+
+    async fn resume_once(
+        &self,
+        url: &str,
+        part: &Path,
+        on_bytes: &impl Fn(u64),
+    ) -> Result<FileDigest, DownloadError> {
+        let offset = fs::metadata(part).map(|meta| meta.len()).unwrap_or(0);
+
+        let mut request = self.client.get(url);
+        if offset > 0 {
+            request = request.header(header::RANGE, format!("bytes={offset}-"));
+        }
+        let mut response = request.send().await?;
+
+        let (mut file, mut hasher, mut size) = match response.status() {
+            StatusCode::PARTIAL_CONTENT => {
+                // the part already on disk is hashed first so the digest covers the whole file
+                let mut file = OpenOptions::new().read(true).append(true).open(part)?;
+                let mut hasher = Sha1::new();
+                let size = io::copy(&mut file, &mut hasher)?;
+                (file, hasher, size)
+            }
+            // the server ignored the range, the whole file is sent again
+            status if status.is_success() => (File::create(part)?, Sha1::new(), 0),
+            status => {
+                if status == StatusCode::RANGE_NOT_SATISFIABLE {
+                    fs::remove_file(part)?;
+                }
+                return Err(DownloadError::Status(status));
+            }
+        };
+
+        // written as it is received so a failed attempt can be resumed
+        while let Some(chunk) = response.chunk().await? {
+            hasher.update(&chunk);
+            size += chunk.len() as u64;
+            file.write_all(&chunk)?;
+            on_bytes(chunk.len() as u64);
+        }
+        Ok(FileDigest {
+            size,
+            sha1: hex(&hasher.finalize()),
+        })
+    }
+
+    /// runs `attempt` until it succeeds, fails with an error that isn't retryable or runs out of retries
+    async fn retry<T>(
+        &self,
+        attempt: impl AsyncFn() -> Result<T, DownloadError>,
+    ) -> Result<T, DownloadError> {
+        let mut delay = self.options.backoff;
+        let mut retries = 0;
+        loop {
+            match attempt().await {
+                Err(err) if err.is_retryable() && retries < self.options.retries => {
+                    retries += 1;
+                    tokio::time::sleep(jitter(delay)).await;
+                    delay *= 2;
+                }
+                output => return output,
+            }
+        }
+    }
+}
+
+/// returns a random duration between half and one and a half times `delay`
+fn jitter(delay: Duration) -> Duration {
+    let random = RandomState::new().build_hasher().finish();
+    delay.mul_f64(0.5 + (random % 1000) as f64 / 1000.0)
+}
+
+/// the path a file is downloaded to before being moved to `path`
+pub fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    path.with_file_name(name)
+}
+
+static DOWNLOADER: OnceLock<Downloader> = OnceLock::new();
+
+/// sets the options used by the launcher's downloads, has no effect once a download was made
+pub fn configure(options: DownloadOptions) -> Result<(), DownloadError> {
+    let _ = DOWNLOADER.set(Downloader::new(options)?);
+    Ok(())
+}
+
+/// the [`Downloader`] used by the launcher, see [`configure`]
+pub fn downloader() -> &'static Downloader {
+    DOWNLOADER.get_or_init(|| {
+        Downloader::new(DownloadOptions::default()).expect("failed to build the HTTP client")
+    })
+}
+
 /// Downloads a file from a given url and returns it as a byte vector
 pub async fn get(url: &str) -> Result<Bytes, DownloadError> {
-    let response = reqwest::get(url).await?;
-    if !response.status().is_success() {
-        return Err(DownloadError::Status(response.status()));
-    }
-    // TODO: return Bytes instead of Vec<u8>
-    let bytes = response.bytes().await?;
-    Ok(bytes)
+    downloader().get(url).await
 }
@@ -1,6 +1,7 @@
-use std::io;
+use std::{fmt::Display, io, path::PathBuf};
 
 use super::download::DownloadError;
+use crate::config::Layer;
 
 #[derive(Debug)]
 pub enum CoreError<'a> {
@@ -9,21 +10,98 @@ pub enum CoreError<'a> {
     IoError(io::Error),
     MinecraftVersionNotFound,
     ProfileNotFound(&'a str),
+    ProfileRunning(&'a str),
+    /// the name can't be used as the name of a profile's directory, such as `..` or a name containing `/`
+    InvalidProfileName(&'a str),
+    /// the profile was never installed, so there is nothing to verify
+    ProfileNotInstalled(&'a str),
+    /// the launcher is offline and the given files have to be downloaded first
+    Offline(Vec<PathBuf>),
     MinecraftFailure(i32),
+    /// a launch hook exited unsuccessfully, contains the name of the hook and its exit code
+    HookFailure(&'static str, i32),
+    /// the version of the profile doesn't support the given quick play entry
+    QuickPlayUnsupported(&'static str),
+    ConfigError(ConfigError),
+    /// the launcher directories can't be found because neither the XDG variables nor `HOME` are set
+    NoHomeDir,
+    /// Mojang doesn't publish the given Java runtime component for this platform
+    JavaRuntimeNotFound(String),
 }
 
-impl From<DownloadError> for CoreError<'static> {
+#[derive(Debug)]
+pub enum ConfigError {
+    InvalidValue {
+        entry: String,
+        value: String,
+        reason: String,
+    },
+    Missing(&'static str),
+    /// only one of the given entries can be set at a time
+    Conflict(&'static str),
+    RamRange {
+        min: u32,
+        max: u32,
+    },
+    /// the entry applies to the whole launcher and can't be set in the given layer
+    LauncherOnly(&'static str, Layer),
+    JavaNotFound(PathBuf),
+    JavaNotExecutable(PathBuf),
+    Malformed(PathBuf, serde_json::Error),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::InvalidValue {
+                entry,
+                value,
+                reason,
+            } => write!(f, "invalid value `{value}` for `{entry}`: {reason}"),
+            ConfigError::Missing(entry) => write!(f, "`{entry}` is not set"),
+            ConfigError::Conflict(entries) => {
+                write!(f, "only one of {entries} can be set at a time")
+            }
+            ConfigError::RamRange { min, max } => write!(
+                f,
+                "`min_ram` ({min} MiB) must not be greater than `max_ram` ({max} MiB)"
+            ),
+            ConfigError::LauncherOnly(entry, layer) => write!(
+                f,
+                "`{entry}` applies to the whole launcher and can't be set in the {layer} layer, \
+                 set it in the global config or the environment instead"
+            ),
+            ConfigError::JavaNotFound(path) => {
+                write!(f, "java executable `{}` does not exist", path.display())
+            }
+            ConfigError::JavaNotExecutable(path) => {
+                write!(f, "java executable `{}` is not executable", path.display())
+            }
+            ConfigError::Malformed(path, err) => {
+                write!(f, "failed to parse `{}`: {err}", path.display())
+            }
+        }
+    }
+}
+
+impl<'a> From<ConfigError> for CoreError<'a> {
+    fn from(value: ConfigError) -> Self {
+        Self::ConfigError(value)
+    }
+}
+
+impl<'a> From<DownloadError> for CoreError<'a> {
     fn from(value: DownloadError) -> Self {
         Self::DownloadError(value)
     }
 }
 
-impl From<std::io::Error> for CoreError<'static> {
+impl<'a> From<std::io::Error> for CoreError<'a> {
     fn from(value: std::io::Error) -> Self {
         Self::IoError(value)
     }
 }
-impl From<zip::result::ZipError> for CoreError<'static> {
+impl<'a> From<zip::result::ZipError> for CoreError<'a> {
     fn from(value: zip::result::ZipError) -> Self {
         Self::ZipError(value)
     }
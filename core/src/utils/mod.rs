@@ -1,5 +1,7 @@
+use std::{fs, io, path::Path};
+
 use serde::Deserialize;
-pub const MULTI_PATH_SEPRATOR: &'static str = if cfg!(target_os = "windows") {
+pub const MULTI_PATH_SEPRATOR: &str = if cfg!(target_os = "windows") {
     ";"
 } else {
     ":"
@@ -21,6 +23,21 @@ pub enum Arch {
     ARM64,
 }
 
-pub(crate) mod download;
+pub mod download;
 pub mod errors;
-pub(crate) mod zip;
+pub mod zip;
+
+/// recursively copies the directory `from` into `to`, creating `to` if it doesn't exist
+pub fn copy_dir_all(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let dest = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &dest)?;
+        } else {
+            fs::copy(entry.path(), dest)?;
+        }
+    }
+    Ok(())
+}
@@ -1,29 +1,103 @@
-use std::{fs, io::Cursor, path::Path};
+use std::{
+    fmt::Display,
+    fs::{self, File},
+    io::{self, BufReader, Read, Seek},
+    path::{Component, Path},
+};
 
 use zip::{result::ZipError, ZipArchive};
 
-pub struct ZipExtractor<'a> {
-    bytes: &'a [u8],
-    exclude: Option<&'a [&'a Path]>,
+/// A glob-style pattern matched against the paths inside an archive
+/// `*` matches any part of a path segment, `?` a single character and `**` any number of segments
+/// a pattern also matches everything inside the directories it matches, so `META-INF/` excludes the whole directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob {
+    segments: Vec<String>,
 }
 
-impl<'a> ZipExtractor<'a> {
-    pub fn new(bytes: &'a [u8]) -> Self {
+impl Glob {
+    pub fn new(pattern: &str) -> Self {
         Self {
-            bytes,
-            exclude: None,
+            segments: pattern
+                .split(['/', '\\'])
+                .filter(|segment| !segment.is_empty())
+                .map(String::from)
+                .collect(),
         }
     }
 
-    pub fn exclude(mut self, exclude: &'a [&'a Path]) -> Self {
-        self.exclude = Some(exclude);
+    pub fn matches(&self, path: &Path) -> bool {
+        let path: Vec<_> = path
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(segment) => segment.to_str(),
+                _ => None,
+            })
+            .collect();
+        let pattern: Vec<_> = self.segments.iter().map(String::as_str).collect();
+        matches_segments(&pattern, &path)
+    }
+}
+
+/// the pattern with its segments separated by `/`, e.g. `META-INF` for `META-INF/`
+impl Display for Glob {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.segments.join("/"))
+    }
+}
+
+/// whether `path` or one of its parents matches `pattern`, both split into segments
+fn matches_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => true,
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| matches_segments(rest, &path[skip..])),
+        Some((segment, rest)) => path.split_first().is_some_and(|(name, path)| {
+            matches_segment(segment.as_bytes(), name.as_bytes()) && matches_segments(rest, path)
+        }),
+    }
+}
+
+fn matches_segment(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|skip| matches_segment(rest, &name[skip..])),
+        Some((b'?', rest)) => name
+            .split_first()
+            .is_some_and(|(_, name)| matches_segment(rest, name)),
+        Some((c, rest)) => name
+            .split_first()
+            .is_some_and(|(n, name)| c == n && matches_segment(rest, name)),
+    }
+}
+
+pub struct ZipExtractor<R> {
+    reader: R,
+    exclude: Vec<Glob>,
+}
+
+impl ZipExtractor<BufReader<File>> {
+    /// reads the archive from the file at `path` instead of loading it in memory
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Ok(Self::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: Read + Seek> ZipExtractor<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            exclude: Vec::new(),
+        }
+    }
+
+    /// skips the entries matching any of the given [`Glob`] patterns
+    pub fn exclude(mut self, exclude: impl IntoIterator<Item = Glob>) -> Self {
+        self.exclude.extend(exclude);
         self
     }
 
     pub fn extract(self, output: &Path) -> Result<(), ZipError> {
-        let exclude = self.exclude.unwrap_or_default();
-        let reader = Cursor::new(self.bytes);
-        let mut archive = ZipArchive::new(reader)?;
+        let mut archive = ZipArchive::new(self.reader)?;
 
         for i in 0..archive.len() {
             let mut file = archive.by_index(i)?;
@@ -33,19 +107,17 @@ impl<'a> ZipExtractor<'a> {
                 None => continue,
             };
 
-            if exclude.contains(&file_path.as_path())
-                || file_path.parent().is_some_and(|p| exclude.contains(&p))
-            {
+            if self.exclude.iter().any(|glob| glob.matches(&file_path)) {
                 continue;
             }
 
             let output = output.join(&file_path);
-            if file_path.is_dir() {
-                fs::create_dir_all(output).unwrap();
+            if file.is_dir() {
+                fs::create_dir_all(output)?;
             } else {
                 if let Some(p) = output.parent() {
                     if !p.exists() {
-                        fs::create_dir_all(&p)?;
+                        fs::create_dir_all(p)?;
                     }
                 }
 
@@ -1,52 +1,197 @@
 use std::{
-    fs::{self},
-    path::Path,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use bytes::Bytes;
 use crab_launcher_api::meta::manifest::{Version, VersionManifest};
 
-use crate::utils::{self, errors::CoreError};
-
-/// parses the global version manifest
-async fn fetch_global_manifest(launcher_root: &Path) -> VersionManifest {
-    let path = launcher_root.join("version_manifest.json");
-    // download version info
-    let res =
-        utils::download::get("https://launchermeta.mojang.com/mc/game/version_manifest.json").await;
-    // if offline use pre-downloaded file
-    if let Ok(res) = res {
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent).expect("failed creating dir");
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{
+    self,
+    download::{Conditional, DownloadError, FileDigest, Validators},
+    errors::CoreError,
+};
+
+const MANIFEST_URL: &str = "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json";
+
+fn cache_path(cache_root: &Path) -> PathBuf {
+    cache_root.join("version_manifest_v2.json")
+}
+
+/// What is known about the cached manifest, stored next to it
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheMeta {
+    #[serde(flatten)]
+    validators: Validators,
+    /// when the cached manifest was last known to be up to date, in seconds since the unix epoch
+    checked_at: u64,
+}
+
+impl CacheMeta {
+    fn path(cache_root: &Path) -> PathBuf {
+        cache_root.join("version_manifest_v2.meta.json")
+    }
+
+    /// returns the metadata of the cached manifest, `None` if there is no cached manifest
+    fn read(cache_root: &Path) -> Option<Self> {
+        if !cache_path(cache_root).exists() {
+            return None;
         }
-        fs::write(&path, res).expect("failed writing file version_manifest.json");
+        let data = fs::read(Self::path(cache_root)).ok()?;
+        serde_json::from_slice(&data).ok()
     }
 
-    let buffer = fs::read_to_string(path).expect("failed reading file version_manifest.json");
-    serde_json::from_str(buffer.as_str()).expect("failed parsing file version_manifest.json")
+    fn write(&self, cache_root: &Path) -> io::Result<()> {
+        fs::write(Self::path(cache_root), serde_json::to_vec_pretty(self)?)
+    }
+
+    fn is_fresh(&self, ttl: Duration) -> bool {
+        now().saturating_sub(self.checked_at) < ttl.as_secs()
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// a floating version that always resolves to the latest release
+pub const LATEST_RELEASE: &str = "latest-release";
+/// a floating version that always resolves to the latest snapshot
+pub const LATEST_SNAPSHOT: &str = "latest-snapshot";
+
+/// returns true if `version` is a floating alias instead of a concrete version id
+pub fn is_alias(version: &str) -> bool {
+    version == LATEST_RELEASE || version == LATEST_SNAPSHOT
 }
 
 #[derive(Debug)]
 pub struct Manifest {
     inner: VersionManifest,
+    cache_root: PathBuf,
 }
 
 impl Manifest {
-    pub async fn fetch(launcher_root: &Path) -> Self {
-        let inner = fetch_global_manifest(launcher_root).await;
-        Self { inner }
+    /// returns the global version manifest cached in `cache_root`, refreshing the cache if it is older than `ttl`
+    /// the manifest is only downloaded again if it changed since it was cached, or if the cached one is corrupted
+    pub async fn fetch(cache_root: &Path, ttl: Duration) -> Result<Self, CoreError<'static>> {
+        let mut cached = match Self::cached(cache_root) {
+            Ok(manifest) => Some(manifest),
+            Err(CoreError::Offline(_)) => None,
+            // deleting it also drops its validators, so it is downloaded again whatever the server says about it
+            Err(_) => {
+                fs::remove_file(cache_path(cache_root))?;
+                None
+            }
+        };
+        let meta = CacheMeta::read(cache_root);
+        if meta.as_ref().is_some_and(|meta| meta.is_fresh(ttl)) {
+            if let Some(manifest) = cached.take() {
+                return Ok(manifest);
+            }
+        }
+
+        let validators = meta.map(|meta| meta.validators).unwrap_or_default();
+        let response = utils::download::downloader()
+            .get_if_modified(MANIFEST_URL, &validators)
+            .await?;
+
+        let (manifest, validators) = match response {
+            Conditional::NotModified => match cached {
+                Some(manifest) => (manifest, validators),
+                None => return Err(DownloadError::Corrupted(MANIFEST_URL.to_string()).into()),
+            },
+            Conditional::Modified(data, validators) => {
+                let inner = serde_json::from_slice(&data)
+                    .map_err(|_| DownloadError::Corrupted(MANIFEST_URL.to_string()))?;
+                fs::create_dir_all(cache_root)?;
+                fs::write(cache_path(cache_root), data)?;
+                let manifest = Self {
+                    inner,
+                    cache_root: cache_root.to_path_buf(),
+                };
+                (manifest, validators)
+            }
+        };
+
+        let meta = CacheMeta {
+            validators,
+            checked_at: now(),
+        };
+        meta.write(cache_root)?;
+        Ok(manifest)
+    }
+
+    /// reads the version manifest cached by [`Manifest::fetch`] without touching the network
+    pub fn cached(cache_root: &Path) -> Result<Self, CoreError<'static>> {
+        let path = cache_path(cache_root);
+        let Ok(data) = fs::read(&path) else {
+            return Err(CoreError::Offline(vec![path]));
+        };
+        let inner = serde_json::from_slice(&data).map_err(io::Error::from)?;
+        Ok(Self {
+            inner,
+            cache_root: cache_root.to_path_buf(),
+        })
     }
 
     pub fn versions(&self) -> impl Iterator<Item = &Version> {
         self.inner.versions.iter()
     }
 
-    /// downloads client.json for a given minecraft version and the client.json contents as a string
+    /// resolves `version` to a concrete version id, floating aliases are resolved to the version they currently point to
+    /// returns `None` if the version doesn't exist
+    pub fn resolve<'a>(&'a self, version: &'a str) -> Option<&'a str> {
+        let id = match version {
+            LATEST_RELEASE => &self.inner.latest.release,
+            LATEST_SNAPSHOT => &self.inner.latest.snapshot,
+            id => id,
+        };
+        self.version(id).map(|x| x.id.as_str())
+    }
+
+    /// returns the version with the concrete id `id`
+    pub fn version(&self, id: &str) -> Option<&Version> {
+        self.versions().find(|x| x.id == id)
+    }
+
+    /// client.json files are cached by their SHA-1, so a republished version is cached next to the previous one
+    fn client_cache_path(&self, version: &Version) -> PathBuf {
+        self.cache_root
+            .join("versions")
+            .join(format!("{}.json", version.sha1.to_lowercase()))
+    }
+
+    /// returns the client.json of a given minecraft version, downloading it if it isn't cached yet
+    /// the file is rejected if it doesn't match the SHA-1 given by the manifest
     pub async fn download_version(&self, version: &str) -> Result<Bytes, CoreError<'static>> {
-        let Some(version) = self.versions().find(|x| x.id == version) else {
+        let Some(version) = self.version(version) else {
             return Err(CoreError::MinecraftVersionNotFound);
         };
-        let res = utils::download::get(&version.url).await?;
-        Ok(res)
+        let matches = |data: &[u8]| {
+            FileDigest::of_bytes(data)
+                .sha1
+                .eq_ignore_ascii_case(&version.sha1)
+        };
+
+        let path = self.client_cache_path(version);
+        if let Ok(data) = fs::read(&path) {
+            if matches(&data) {
+                return Ok(Bytes::from(data));
+            }
+        }
+
+        let data = utils::download::get(&version.url).await?;
+        if !matches(&data) {
+            return Err(DownloadError::Corrupted(version.url.clone()).into());
+        }
+        fs::create_dir_all(self.cache_root.join("versions"))?;
+        fs::write(path, &data)?;
+        Ok(data)
     }
 }
@@ -0,0 +1,127 @@
+mod common;
+
+use std::{fs, path::Path};
+
+use common::TempDir;
+use crab_launcher_api::meta::client::Client;
+use crab_launcher_core::{client, utils::download::FileDigest};
+use serde_json::{json, Map, Value};
+
+/// assets named like the ones of the `pre-1.6` and `legacy` indexes, with the content of their objects
+const ASSETS: &[(&str, &[u8])] = &[
+    ("READ_ME_I_AM_VERY_IMPORTANT.txt", b"read me"),
+    ("sound/step/grass1.ogg", b"grass"),
+    ("music/calm1.ogg", b"calm"),
+    ("lang/en_US.lang", b"language.name=English"),
+];
+
+/// a client.json using the asset index `id`, only the fields read when laying out assets matter
+fn client(id: &str) -> Client {
+    let download = json!({ "sha1": "", "size": 0, "url": "" });
+    serde_json::from_value(json!({
+        "minecraftArguments": "--assetsDir ${game_assets}",
+        "assetIndex": download,
+        "assets": id,
+        "downloads": { "client": download },
+        "libraries": [],
+        "mainClass": "net.minecraft.client.Minecraft",
+    }))
+    .unwrap()
+}
+
+/// writes the asset index `id` with the given flags, and the objects it lists, as downloaded by an install
+fn write_index(assets_root: &Path, id: &str, flags: Value) {
+    let mut index = Map::new();
+    let mut objects = Map::new();
+    for (name, data) in ASSETS {
+        let digest = FileDigest::of_bytes(data);
+        let object = assets_root
+            .join("objects")
+            .join(&digest.sha1[..2])
+            .join(&digest.sha1);
+        fs::create_dir_all(object.parent().unwrap()).unwrap();
+        fs::write(object, data).unwrap();
+        objects.insert(
+            name.to_string(),
+            json!({ "hash": digest.sha1, "size": digest.size }),
+        );
+    }
+    index.insert("objects".to_string(), Value::Object(objects));
+    if let Value::Object(flags) = flags {
+        index.extend(flags);
+    }
+
+    let path = assets_root.join("indexes").join(format!("{id}.json"));
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, Value::Object(index).to_string()).unwrap();
+}
+
+fn assert_laid_out(dir: &Path) {
+    for (name, data) in ASSETS {
+        assert_eq!(fs::read(dir.join(name)).unwrap(), *data, "{name}");
+    }
+}
+
+#[test]
+fn virtual_assets_are_shared_by_name() {
+    let root = TempDir::new("virtual");
+    let (assets, profile) = (root.join("assets"), root.join("profile"));
+    write_index(&assets, "legacy", json!({ "virtual": true }));
+    let client = client("legacy");
+
+    client::place_assets(&assets, &client, &profile).unwrap();
+    let dir = assets.join("virtual/legacy");
+    assert_laid_out(&dir);
+    assert_eq!(client::game_assets_dir(&assets, &client, &profile), dir);
+    assert!(!profile.join("resources").exists());
+}
+
+#[test]
+fn pre_1_6_assets_are_mapped_to_resources() {
+    let root = TempDir::new("resources");
+    let (assets, profile) = (root.join("assets"), root.join("profile"));
+    write_index(&assets, "pre-1.6", json!({ "map_to_resources": true }));
+    let client = client("pre-1.6");
+
+    client::place_assets(&assets, &client, &profile).unwrap();
+    let dir = profile.join("resources");
+    assert_laid_out(&dir);
+    assert_eq!(client::game_assets_dir(&assets, &client, &profile), dir);
+    assert!(!assets.join("virtual").exists());
+}
+
+#[test]
+fn hashed_assets_are_read_from_the_objects() {
+    let root = TempDir::new("hashed");
+    let (assets, profile) = (root.join("assets"), root.join("profile"));
+    write_index(&assets, "5", json!({}));
+    let client = client("5");
+
+    client::place_assets(&assets, &client, &profile).unwrap();
+    assert_eq!(client::game_assets_dir(&assets, &client, &profile), assets);
+    assert!(!assets.join("virtual").exists());
+    assert!(!profile.join("resources").exists());
+}
+
+#[test]
+fn corrupted_assets_are_placed_again() {
+    let root = TempDir::new("corrupted");
+    let (assets, profile) = (root.join("assets"), root.join("profile"));
+    write_index(&assets, "pre-1.6", json!({ "map_to_resources": true }));
+    let client = client("pre-1.6");
+    client::place_assets(&assets, &client, &profile).unwrap();
+
+    // same size as the asset, so only its content tells it apart
+    let grass = profile.join("resources/sound/step/grass1.ogg");
+    fs::remove_file(&grass).unwrap();
+    fs::write(&grass, b"GRASS").unwrap();
+    // an intact copy, e.g. one made on a filesystem without hardlinks, is kept
+    let calm = profile.join("resources/music/calm1.ogg");
+    fs::remove_file(&calm).unwrap();
+    fs::write(&calm, b"calm").unwrap();
+    let modified = fs::metadata(&calm).unwrap().modified().unwrap();
+
+    client::place_assets(&assets, &client, &profile).unwrap();
+    assert_laid_out(&profile.join("resources"));
+    assert_eq!(fs::metadata(&calm).unwrap().modified().unwrap(), modified);
+}
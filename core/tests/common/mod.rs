@@ -0,0 +1,151 @@
+//! helpers shared by the integration tests, every test binary only uses some of them
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+/// A fresh directory for the files of a test, deleted with everything inside it once dropped
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("crab-launcher-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// writes `data` to `path`, creating its parents, and returns `path`
+pub fn write(path: &Path, data: impl AsRef<[u8]>) -> PathBuf {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, data).unwrap();
+    path.to_path_buf()
+}
+
+/// A request received by a [`StandIn`]
+pub struct Request {
+    /// the number of requests received before this one
+    pub index: usize,
+    /// the request line and headers
+    pub head: String,
+}
+
+impl Request {
+    /// the path of the requested url
+    pub fn path(&self) -> &str {
+        self.head.split(' ').nth(1).unwrap_or_default()
+    }
+
+    pub fn header(&self, name: &str) -> Option<String> {
+        header(&self.head, name)
+    }
+}
+
+/// A local HTTP server standing in for Mojang's servers, each request is answered by the given function
+pub struct StandIn {
+    base: String,
+    /// the request line and headers of each request received, in order
+    heads: Arc<Mutex<Vec<String>>>,
+}
+
+impl StandIn {
+    pub fn start(answer: impl Fn(&Request, &mut TcpStream) + Send + Sync + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let heads = Arc::new(Mutex::new(Vec::new()));
+
+        let answer = Arc::new(answer);
+        let received = heads.clone();
+        thread::spawn(move || {
+            for (index, stream) in listener.incoming().enumerate() {
+                let (answer, received) = (answer.clone(), received.clone());
+                thread::spawn(move || {
+                    let mut stream = stream.unwrap();
+                    let head = read_head(&stream);
+                    received.lock().unwrap().push(head.clone());
+                    answer(&Request { index, head }, &mut stream);
+                });
+            }
+        });
+
+        Self { base, heads }
+    }
+
+    /// the url of `path` on this server, `path` starts with a `/`
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.base)
+    }
+
+    pub fn heads(&self) -> Vec<String> {
+        self.heads.lock().unwrap().clone()
+    }
+
+    /// the path of each request received, in order
+    pub fn paths(&self) -> Vec<String> {
+        self.heads()
+            .into_iter()
+            .map(|head| head.split(' ').nth(1).unwrap_or_default().to_string())
+            .collect()
+    }
+}
+
+fn read_head(stream: &TcpStream) -> String {
+    let mut head = String::new();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        if line.trim().is_empty() {
+            break;
+        }
+        head.push_str(&line);
+    }
+    head
+}
+
+/// the value of the header `name` in the request `head`
+pub fn header(head: &str, name: &str) -> Option<String> {
+    head.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.eq_ignore_ascii_case(name)
+            .then(|| value.trim().to_string())
+    })
+}
+
+/// writes the head of a response announcing `len` bytes, `headers` are extra `Name: value` lines
+pub fn write_head(stream: &mut TcpStream, status: &str, len: usize, headers: &[String]) {
+    let mut head = format!("HTTP/1.1 {status}\r\nContent-Length: {len}\r\nConnection: close\r\n");
+    for header in headers {
+        head.push_str(&format!("{header}\r\n"));
+    }
+    head.push_str("\r\n");
+    let _ = stream.write_all(head.as_bytes());
+}
+
+/// writes a whole response with `body`
+pub fn respond(stream: &mut TcpStream, status: &str, body: &[u8]) {
+    write_head(stream, status, body.len(), &[]);
+    let _ = stream.write_all(body);
+}
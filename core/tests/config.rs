@@ -0,0 +1,110 @@
+use crab_launcher_core::{
+    config::{Config, Layer, LayeredConfig},
+    utils::errors::ConfigError,
+};
+
+fn config(pairs: &[(&str, &str)]) -> Config {
+    let pairs: Vec<_> = pairs
+        .iter()
+        .map(|(entry, value)| (entry.to_string(), value.to_string()))
+        .collect();
+    Config::from_pairs(&pairs).unwrap()
+}
+
+#[test]
+fn rejects_zero_however_it_is_written() {
+    for zero in ["0", "00", "+0", " 0"] {
+        for entry in ["resolution_width", "download_timeout", "max_downloads"] {
+            let result = config(&[(entry, zero)]).check(entry);
+            assert!(
+                matches!(result, Err(ConfigError::InvalidValue { .. })),
+                "{entry} = {zero:?} was accepted"
+            );
+        }
+    }
+    assert!(config(&[("resolution_width", "010")])
+        .check("resolution_width")
+        .is_ok());
+}
+
+#[test]
+fn quick_play_overrides_lower_layers() {
+    let layers = LayeredConfig::default()
+        .with(
+            Layer::Profile,
+            config(&[
+                ("current_java_path", "/bin/sh"),
+                ("quick_play_singleplayer", "world"),
+            ]),
+        )
+        .with(
+            Layer::Override,
+            config(&[("quick_play_multiplayer", "localhost")]),
+        );
+
+    let resolved = layers.resolve();
+    assert_eq!(
+        resolved.quick_play_multiplayer().map(String::as_str),
+        Some("localhost")
+    );
+    assert_eq!(resolved.quick_play_singleplayer(), None);
+    assert!(resolved.validate().is_ok());
+
+    // entries set in the same layer still conflict
+    let both = config(&[
+        ("current_java_path", "/bin/sh"),
+        ("quick_play_singleplayer", "world"),
+        ("quick_play_multiplayer", "localhost"),
+    ]);
+    assert!(matches!(both.validate(), Err(ConfigError::Conflict(_))));
+}
+
+#[test]
+fn launcher_entries_are_only_read_from_launcher_layers() {
+    for entry in ["download_retries", "manifest_ttl", "java_runtimes_url"] {
+        let launcher = || config(&[(entry, "3")]);
+        for layer in [Layer::Global, Layer::Env] {
+            let layers = LayeredConfig::default().with(layer, launcher());
+            assert!(layers.check_layers().is_ok());
+        }
+        for layer in [Layer::Profile, Layer::Override] {
+            let layers = LayeredConfig::default().with(layer, launcher());
+            assert!(matches!(
+                layers.check_layers(),
+                Err(ConfigError::LauncherOnly(e, l)) if e == entry && l == layer
+            ));
+        }
+    }
+}
+
+#[test]
+fn empty_mirror_lists_keep_the_original_url() {
+    let config: Config = serde_json::from_str(
+        r#"{ "mirrors": { "https://libraries.minecraft.net/": [], "https://a.com/": ["https://m.com/"] } }"#,
+    )
+    .unwrap();
+    let options = config.download_options();
+
+    let url = "https://libraries.minecraft.net/org/lwjgl/lwjgl.jar";
+    assert_eq!(options.rewrite(url), [url]);
+    assert_eq!(options.rewrite("https://a.com/x"), ["https://m.com/x"]);
+}
+
+#[test]
+fn http_settings_are_only_read_from_launcher_layers() {
+    let settings = [
+        ("mirrors", "https://a.com/=https://m.com/"),
+        ("http_proxy", "http://proxy:3128"),
+        ("https_proxy", "http://proxy:3128"),
+        ("no_proxy", "localhost"),
+        ("ca_bundles", "/etc/ssl/ca.pem"),
+        ("connect_timeout", "5"),
+    ];
+    for (entry, value) in settings {
+        let layers = LayeredConfig::default().with(Layer::Override, config(&[(entry, value)]));
+        assert!(matches!(
+            layers.check_layers(),
+            Err(ConfigError::LauncherOnly(e, Layer::Override)) if e == entry
+        ));
+    }
+}
@@ -0,0 +1,117 @@
+use std::{
+    env,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crab_launcher_core::{
+    dirs::{LauncherDirs, ROOT_ENV},
+    utils::errors::CoreError,
+};
+
+/// the variables read by the tests, the environment is shared by the whole process so they run one at a time
+const VARS: [&str; 5] = [
+    "HOME",
+    "XDG_DATA_HOME",
+    "XDG_CONFIG_HOME",
+    "XDG_CACHE_HOME",
+    ROOT_ENV,
+];
+static ENV: Mutex<()> = Mutex::new(());
+
+/// runs `test` with only the given variables of [`VARS`] set, restoring them afterwards
+fn with_vars(vars: &[(&str, &str)], test: impl FnOnce()) {
+    let _guard = ENV.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let saved: Vec<(&str, Option<OsString>)> =
+        VARS.iter().map(|var| (*var, env::var_os(var))).collect();
+    for var in VARS {
+        env::remove_var(var);
+    }
+    for (var, value) in vars {
+        env::set_var(var, value);
+    }
+
+    test();
+
+    for (var, value) in saved {
+        match value {
+            Some(value) => env::set_var(var, value),
+            None => env::remove_var(var),
+        }
+    }
+}
+
+fn assert_dirs(dirs: &LauncherDirs, data: &str, config: &str, cache: &str) {
+    assert_eq!(dirs.data(), Path::new(data));
+    assert_eq!(dirs.config(), Path::new(config));
+    assert_eq!(dirs.cache(), Path::new(cache));
+}
+
+#[test]
+fn rooted_and_portable_keep_everything_in_one_directory() {
+    let dirs = LauncherDirs::rooted(Path::new("/games/crab"));
+    assert_dirs(&dirs, "/games/crab", "/games/crab", "/games/crab");
+    assert_dirs(
+        &LauncherDirs::portable(),
+        "launcher",
+        "launcher",
+        "launcher",
+    );
+}
+
+#[test]
+fn xdg_falls_back_to_home() {
+    with_vars(&[("HOME", "/home/steve")], || {
+        assert_dirs(
+            &LauncherDirs::xdg().unwrap(),
+            "/home/steve/.local/share/crablauncher",
+            "/home/steve/.config/crablauncher",
+            "/home/steve/.cache/crablauncher",
+        );
+    });
+}
+
+#[test]
+fn xdg_variables_take_precedence_over_home() {
+    let vars = [
+        ("HOME", "/home/steve"),
+        ("XDG_DATA_HOME", "/data"),
+        ("XDG_CACHE_HOME", "/tmp/cache"),
+        // relative paths are ignored
+        ("XDG_CONFIG_HOME", "config"),
+    ];
+    with_vars(&vars, || {
+        assert_dirs(
+            &LauncherDirs::xdg().unwrap(),
+            "/data/crablauncher",
+            "/home/steve/.config/crablauncher",
+            "/tmp/cache/crablauncher",
+        );
+    });
+}
+
+#[test]
+fn xdg_without_home_is_an_error() {
+    with_vars(&[], || {
+        assert!(matches!(LauncherDirs::xdg(), Err(CoreError::NoHomeDir)));
+    });
+    // every directory has to be found, not only some of them
+    with_vars(&[("XDG_DATA_HOME", "/data")], || {
+        assert!(matches!(LauncherDirs::xdg(), Err(CoreError::NoHomeDir)));
+    });
+}
+
+#[test]
+fn root_variable_replaces_xdg() {
+    with_vars(&[(ROOT_ENV, "/games/crab")], || {
+        let dirs = LauncherDirs::from_env().unwrap();
+        assert_eq!(dirs, LauncherDirs::rooted(&PathBuf::from("/games/crab")));
+    });
+    with_vars(&[(ROOT_ENV, ""), ("HOME", "/home/steve")], || {
+        assert_eq!(
+            LauncherDirs::from_env().unwrap(),
+            LauncherDirs::xdg().unwrap()
+        );
+    });
+}
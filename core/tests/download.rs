@@ -0,0 +1,438 @@
+mod common;
+
+use std::{fs, io::Write, net::TcpStream, path::PathBuf, thread, time::Duration};
+
+use common::{header, respond, write_head, Request, StandIn, TempDir};
+
+use crab_launcher_core::utils::download::{
+    part_path, Conditional, DownloadError, DownloadOptions, Downloader, FileDigest, Validators,
+};
+
+const BODY: &[u8] = b"the quick brown fox jumps over the lazy dog";
+const BODY_SHA1: &str = "16312751ef9307c3fd1afbcb993cdc80464ba0f1";
+
+fn body_digest() -> FileDigest {
+    FileDigest {
+        size: BODY.len() as u64,
+        sha1: BODY_SHA1.to_string(),
+    }
+}
+
+/// What the stand-in server does with a request
+#[derive(Debug, Clone, Copy)]
+enum Reply {
+    /// answers with the given status and no body
+    Status(u16),
+    /// sends the headers of the file but only the given number of bytes of it before closing the connection
+    Truncate(usize),
+    /// never answers
+    Stall,
+    /// sends the file, honoring the `Range` header
+    Serve,
+    /// sends the whole file, ignoring the `Range` header
+    IgnoreRange,
+    /// sends the file with its validators, or 304 if the request's validators match them
+    Validated,
+}
+
+const ETAG: &str = "\"v1\"";
+const LAST_MODIFIED: &str = "Wed, 21 Oct 2015 07:28:00 GMT";
+
+/// starts a stand-in server answering each request with the next reply of `script`
+/// the script's last reply is repeated once it runs out, the file is served at `/file`
+fn scripted(script: &[Reply]) -> StandIn {
+    let script = script.to_vec();
+    StandIn::start(move |request, stream| {
+        answer(stream, script[request.index.min(script.len() - 1)], request)
+    })
+}
+
+/// the `Range` header of each request received by `server`, in order
+fn ranges(server: &StandIn) -> Vec<Option<String>> {
+    server
+        .heads()
+        .iter()
+        .map(|head| header(head, "range"))
+        .collect()
+}
+
+fn answer(stream: &mut TcpStream, reply: Reply, request: &Request) {
+    let offset = request
+        .header("range")
+        .as_deref()
+        .and_then(|range| range.strip_prefix("bytes="))
+        .and_then(|range| range.strip_suffix('-'))
+        .map(|offset| offset.parse::<usize>().unwrap());
+
+    match reply {
+        Reply::Status(code) => respond(stream, &format!("{code} Failure"), &[]),
+        Reply::Truncate(len) => {
+            write_head(stream, "200 OK", BODY.len(), &[]);
+            let _ = stream.write_all(&BODY[..len]);
+        }
+        Reply::Stall => thread::sleep(Duration::from_secs(5)),
+        Reply::Serve => match offset {
+            Some(offset) => respond(stream, "206 Partial Content", &BODY[offset..]),
+            None => respond(stream, "200 OK", BODY),
+        },
+        Reply::Validated => {
+            if request.header("if-none-match").as_deref() == Some(ETAG)
+                || request.header("if-modified-since").as_deref() == Some(LAST_MODIFIED)
+            {
+                respond(stream, "304 Not Modified", &[]);
+                return;
+            }
+            let validators = [
+                format!("ETag: {ETAG}"),
+                format!("Last-Modified: {LAST_MODIFIED}"),
+            ];
+            write_head(stream, "200 OK", BODY.len(), &validators);
+            let _ = stream.write_all(BODY);
+        }
+        Reply::IgnoreRange => respond(stream, "200 OK", BODY),
+    }
+}
+
+fn options(retries: u32) -> DownloadOptions {
+    DownloadOptions {
+        retries,
+        backoff: Duration::from_millis(1),
+        read_timeout: Duration::from_millis(500),
+        ..Default::default()
+    }
+}
+
+fn downloader(retries: u32) -> Downloader {
+    Downloader::new(options(retries)).unwrap()
+}
+
+#[tokio::test]
+async fn retries_server_errors() {
+    let server = scripted(&[Reply::Status(500), Reply::Status(503), Reply::Serve]);
+    let data = downloader(5).get(&server.url("/file")).await.unwrap();
+    assert_eq!(&data[..], BODY);
+    assert_eq!(ranges(&server).len(), 3);
+}
+
+#[tokio::test]
+async fn gives_up_after_the_configured_retries() {
+    let server = scripted(&[Reply::Status(502)]);
+    let err = downloader(2).get(&server.url("/file")).await.unwrap_err();
+    assert!(matches!(err, DownloadError::Status(status) if status == 502));
+    assert_eq!(ranges(&server).len(), 3);
+}
+
+#[tokio::test]
+async fn does_not_retry_client_errors() {
+    let server = scripted(&[Reply::Status(404), Reply::Serve]);
+    let err = downloader(5).get(&server.url("/file")).await.unwrap_err();
+    assert!(matches!(err, DownloadError::Status(status) if status == 404));
+    assert_eq!(ranges(&server).len(), 1);
+}
+
+#[tokio::test]
+async fn retries_stalled_downloads() {
+    let server = scripted(&[Reply::Stall, Reply::Serve]);
+    let data = downloader(5).get(&server.url("/file")).await.unwrap();
+    assert_eq!(&data[..], BODY);
+}
+
+#[tokio::test]
+async fn resumes_interrupted_downloads() {
+    let dir = TempDir::new("resume");
+    let path = dir.join("file");
+    let server = scripted(&[Reply::Truncate(10), Reply::Serve]);
+
+    downloader(5)
+        .download_to(&server.url("/file"), &path)
+        .await
+        .unwrap();
+    assert_eq!(fs::read(&path).unwrap(), BODY);
+    assert!(!part_path(&path).exists());
+    assert_eq!(ranges(&server), [None, Some("bytes=10-".to_string())]);
+}
+
+#[tokio::test]
+async fn resumes_from_an_existing_part_file() {
+    let dir = TempDir::new("part");
+    let path = dir.join("file");
+    fs::write(part_path(&path), &BODY[..20]).unwrap();
+    let server = scripted(&[Reply::Serve]);
+
+    downloader(0)
+        .download_to(&server.url("/file"), &path)
+        .await
+        .unwrap();
+    assert_eq!(fs::read(&path).unwrap(), BODY);
+    assert_eq!(ranges(&server), [Some("bytes=20-".to_string())]);
+}
+
+#[tokio::test]
+async fn restarts_when_the_range_is_ignored() {
+    let dir = TempDir::new("ignored");
+    let path = dir.join("file");
+    fs::write(part_path(&path), b"stale data").unwrap();
+    let server = scripted(&[Reply::IgnoreRange]);
+
+    downloader(0)
+        .download_to(&server.url("/file"), &path)
+        .await
+        .unwrap();
+    assert_eq!(fs::read(&path).unwrap(), BODY);
+}
+
+#[tokio::test]
+async fn restarts_when_the_range_is_not_satisfiable() {
+    let dir = TempDir::new("unsatisfiable");
+    let path = dir.join("file");
+    fs::write(part_path(&path), b"stale data").unwrap();
+    let server = scripted(&[Reply::Status(416), Reply::Serve]);
+
+    downloader(1)
+        .download_to(&server.url("/file"), &path)
+        .await
+        .unwrap();
+    assert_eq!(fs::read(&path).unwrap(), BODY);
+    assert_eq!(ranges(&server)[1], None);
+}
+
+#[tokio::test]
+async fn leaves_nothing_in_place_on_failure() {
+    let dir = TempDir::new("failure");
+    let path = dir.join("file");
+    let server = scripted(&[Reply::Truncate(10)]);
+
+    assert!(downloader(1)
+        .download_to(&server.url("/file"), &path)
+        .await
+        .is_err());
+    assert!(!path.exists());
+}
+
+#[tokio::test]
+async fn hashes_resumed_downloads_as_a_whole() {
+    let dir = TempDir::new("digest");
+    let part = part_path(&dir.join("file"));
+    let server = scripted(&[Reply::Truncate(10), Reply::Serve]);
+
+    let digest = downloader(1)
+        .download_part(&server.url("/file"), &part, |_| {})
+        .await
+        .unwrap();
+    assert_eq!(digest, body_digest());
+    assert_eq!(FileDigest::of(&part).unwrap(), digest);
+}
+
+#[tokio::test]
+async fn accepts_files_matching_their_digest() {
+    let dir = TempDir::new("verified");
+    let path = dir.join("file");
+    let server = scripted(&[Reply::Serve]);
+
+    let expected = FileDigest {
+        sha1: BODY_SHA1.to_uppercase(),
+        ..body_digest()
+    };
+    downloader(0)
+        .download_verified(&server.url("/file"), &path, &expected, |_| {})
+        .await
+        .unwrap();
+    assert_eq!(fs::read(&path).unwrap(), BODY);
+}
+
+#[tokio::test]
+async fn rejects_files_not_matching_their_digest() {
+    let dir = TempDir::new("rejected");
+    let path = dir.join("file");
+    fs::write(&path, b"previous version").unwrap();
+    let server = scripted(&[Reply::Serve]);
+
+    let expected = FileDigest {
+        sha1: "0".repeat(40),
+        ..body_digest()
+    };
+    let err = downloader(0)
+        .download_verified(&server.url("/file"), &path, &expected, |_| {})
+        .await
+        .unwrap_err();
+    assert!(matches!(err, DownloadError::Corrupted(url) if url == server.url("/file")));
+    assert_eq!(fs::read(&path).unwrap(), b"previous version");
+    assert!(!part_path(&path).exists());
+}
+
+#[test]
+fn rewrites_the_longest_matching_prefix() {
+    let options = DownloadOptions {
+        mirrors: vec![
+            (
+                "https://a.com/".to_string(),
+                vec!["https://m.com/".to_string()],
+            ),
+            (
+                "https://a.com/libs/".to_string(),
+                vec![
+                    "https://m.com/l/".to_string(),
+                    "https://a.com/libs/".to_string(),
+                ],
+            ),
+        ],
+        ..Default::default()
+    };
+    assert_eq!(
+        options.rewrite("https://a.com/x.json"),
+        ["https://m.com/x.json"]
+    );
+    assert_eq!(
+        options.rewrite("https://a.com/libs/y.jar"),
+        ["https://m.com/l/y.jar", "https://a.com/libs/y.jar"]
+    );
+    assert_eq!(options.rewrite("https://b.com/z"), ["https://b.com/z"]);
+}
+
+#[tokio::test]
+async fn downloads_from_mirrors() {
+    let server = scripted(&[Reply::Serve]);
+    let downloader = Downloader::new(DownloadOptions {
+        mirrors: vec![("https://mojang.invalid/".to_string(), vec![server.url("/")])],
+        ..options(0)
+    })
+    .unwrap();
+
+    let data = downloader.get("https://mojang.invalid/file").await.unwrap();
+    assert_eq!(&data[..], BODY);
+}
+
+#[tokio::test]
+async fn falls_back_to_the_next_mirror() {
+    let missing = scripted(&[Reply::Status(404)]);
+    let broken = scripted(&[Reply::Truncate(10)]);
+    let working = scripted(&[Reply::Serve]);
+    let downloader = Downloader::new(DownloadOptions {
+        mirrors: vec![(
+            "https://mojang.invalid/".to_string(),
+            vec![missing.url("/"), broken.url("/"), working.url("/")],
+        )],
+        ..options(1)
+    })
+    .unwrap();
+
+    let dir = TempDir::new("mirrors");
+    let path = dir.join("file");
+    downloader
+        .download_to("https://mojang.invalid/file", &path)
+        .await
+        .unwrap();
+    assert_eq!(fs::read(&path).unwrap(), BODY);
+    assert_eq!(ranges(&missing).len(), 1);
+    assert_eq!(ranges(&broken).len(), 2);
+    // the part downloaded from the broken mirror is resumed from the working one
+    assert_eq!(ranges(&working), [Some("bytes=10-".to_string())]);
+}
+
+#[tokio::test]
+async fn sends_the_launcher_user_agent() {
+    let server = scripted(&[Reply::Serve]);
+    downloader(0).get(&server.url("/file")).await.unwrap();
+
+    let user_agent = header(&server.heads()[0], "user-agent").unwrap();
+    assert!(user_agent.starts_with("CrabLauncher/"), "{user_agent}");
+}
+
+#[tokio::test]
+async fn downloads_through_the_proxy() {
+    let proxy = scripted(&[Reply::Serve]);
+    let downloader = Downloader::new(DownloadOptions {
+        http_proxy: Some(proxy.url("/")),
+        ..options(0)
+    })
+    .unwrap();
+
+    let data = downloader.get("http://mojang.invalid/file").await.unwrap();
+    assert_eq!(&data[..], BODY);
+    assert!(proxy.heads()[0].starts_with("GET http://mojang.invalid/file "));
+}
+
+#[tokio::test]
+async fn bypasses_the_proxy_for_excluded_hosts() {
+    let proxy = scripted(&[Reply::Status(502)]);
+    let server = scripted(&[Reply::Serve]);
+    let downloader = Downloader::new(DownloadOptions {
+        http_proxy: Some(proxy.url("/")),
+        no_proxy: Some("example.com,127.0.0.1".to_string()),
+        ..options(0)
+    })
+    .unwrap();
+
+    downloader.get(&server.url("/file")).await.unwrap();
+    assert!(proxy.heads().is_empty());
+}
+
+#[test]
+fn rejects_invalid_settings() {
+    let missing_ca = DownloadOptions {
+        ca_bundles: vec![PathBuf::from("/nonexistent/ca.pem")],
+        ..options(0)
+    };
+    assert!(matches!(
+        Downloader::new(missing_ca),
+        Err(DownloadError::Settings(_))
+    ));
+
+    let dir = TempDir::new("invalid-ca");
+    let invalid_pem = dir.join("ca.pem");
+    fs::write(
+        &invalid_pem,
+        "-----BEGIN CERTIFICATE-----\nnot base64\n-----END CERTIFICATE-----\n",
+    )
+    .unwrap();
+    let invalid_ca = DownloadOptions {
+        ca_bundles: vec![invalid_pem],
+        ..options(0)
+    };
+    assert!(matches!(
+        Downloader::new(invalid_ca),
+        Err(DownloadError::Settings(_))
+    ));
+}
+
+#[tokio::test]
+async fn returns_the_validators_of_the_response() {
+    let server = scripted(&[Reply::Validated]);
+    let response = downloader(0)
+        .get_if_modified(&server.url("/file"), &Validators::default())
+        .await
+        .unwrap();
+
+    let Conditional::Modified(data, validators) = response else {
+        panic!("expected the file to be sent");
+    };
+    assert_eq!(&data[..], BODY);
+    assert_eq!(validators.etag.as_deref(), Some(ETAG));
+    assert_eq!(validators.last_modified.as_deref(), Some(LAST_MODIFIED));
+}
+
+#[tokio::test]
+async fn skips_unmodified_files() {
+    let server = scripted(&[Reply::Validated]);
+    let downloader = downloader(0);
+
+    let by_etag = Validators {
+        etag: Some(ETAG.to_string()),
+        last_modified: None,
+    };
+    let by_date = Validators {
+        etag: None,
+        last_modified: Some(LAST_MODIFIED.to_string()),
+    };
+    for validators in [by_etag, by_date] {
+        let response = downloader
+            .get_if_modified(&server.url("/file"), &validators)
+            .await
+            .unwrap();
+        assert!(matches!(response, Conditional::NotModified));
+    }
+    assert_eq!(
+        header(&server.heads()[0], "if-none-match").as_deref(),
+        Some(ETAG)
+    );
+}
@@ -0,0 +1,193 @@
+mod common;
+
+use std::{
+    fs::{self, File},
+    path::Path,
+};
+
+use common::{write, TempDir};
+use crab_launcher_core::{
+    config::Layer,
+    dirs::LauncherDirs,
+    env::Env,
+    utils::{
+        download::FileDigest,
+        errors::{ConfigError, CoreError},
+    },
+};
+use serde_json::json;
+
+/// the directories of a launcher rooted in `root`, with an empty global config
+fn dirs(root: &TempDir) -> LauncherDirs {
+    fs::write(root.join("config.json"), "{}").unwrap();
+    LauncherDirs::rooted(root)
+}
+
+/// an offline launcher with a single profile called `game`
+async fn env_with_profile(dirs: &LauncherDirs) -> Env<'_> {
+    let mut env = Env::new(dirs, true).unwrap();
+    env.add("game", "1.20.1").await.unwrap();
+    env
+}
+
+fn launcher_only(err: CoreError, entry: &str, layer: Layer) -> bool {
+    matches!(err, CoreError::ConfigError(ConfigError::LauncherOnly(e, l)) if e == entry && l == layer)
+}
+
+#[tokio::test]
+async fn rejects_launcher_entries_in_profiles() {
+    let root = TempDir::new("profile-entries");
+    let dirs = dirs(&root);
+    let mut env = env_with_profile(&dirs).await;
+
+    let err = env
+        .edit("game", "download_retries", Some("3".to_string()))
+        .unwrap_err();
+    assert!(launcher_only(err, "download_retries", Layer::Profile));
+    assert!(env
+        .edit("game", "max_ram", Some("4096".to_string()))
+        .is_ok());
+}
+
+#[tokio::test]
+async fn rejects_launcher_entries_in_overrides() {
+    let root = TempDir::new("override-entries");
+    let dirs = dirs(&root);
+    let env = env_with_profile(&dirs).await;
+
+    let overrides = [("download_timeout".to_string(), "5".to_string())];
+    let err = env.launch("game", &overrides).unwrap_err();
+    assert!(launcher_only(err, "download_timeout", Layer::Override));
+    let err = env.config_layers("game", &overrides).unwrap_err();
+    assert!(launcher_only(err, "download_timeout", Layer::Override));
+}
+
+#[tokio::test]
+async fn gc_collects_unused_natives() {
+    let root = TempDir::new("natives");
+    let dirs = dirs(&root);
+    let env = env_with_profile(&dirs).await;
+    let download = json!({ "sha1": "", "size": 0, "url": "" });
+    let client = json!({
+        "arguments": { "game": [], "jvm": [] },
+        "assetIndex": download,
+        "assets": "5",
+        "downloads": { "client": download },
+        "libraries": [],
+        "mainClass": "net.minecraft.client.main.Main",
+    });
+    write(&root.join("profiles/game/client.json"), client.to_string());
+
+    // a version without natives hashes nothing, the first 12 digits of the sha1 of nothing name its directory
+    let current = write(
+        &root.join("natives/game/1.20.1-da39a3ee5e6b/.extracted"),
+        "",
+    );
+    let previous = write(
+        &root.join("natives/game/1.19.4-0123456789ab/liblwjgl.so"),
+        "",
+    );
+    let deleted = write(
+        &root.join("natives/deleted/1.20.1-da39a3ee5e6b/liblwjgl.so"),
+        "",
+    );
+    let legacy = write(&root.join("profiles/deleted/.natives/liblwjgl.so"), "");
+    // folders named `natives` in game directories belong to the game or its mods
+    let game = write(&root.join("profiles/game/natives/mod.so"), "");
+    let deleted_game = write(&root.join("profiles/deleted/natives/mod.so"), "");
+
+    let garbage = env.gc(false).unwrap();
+    let mut files: Vec<_> = garbage.files().map(Path::to_path_buf).collect();
+    files.sort();
+    let mut expected = vec![previous, deleted, legacy];
+    expected.sort();
+    assert_eq!(files, expected);
+
+    assert!(current.exists());
+    assert!(game.exists());
+    assert!(deleted_game.exists());
+    assert!(!root.join("natives/deleted").exists());
+    assert!(!root.join("natives/game/1.19.4-0123456789ab").exists());
+}
+
+#[tokio::test]
+async fn install_keeps_only_the_current_natives() {
+    let root = TempDir::new("extract");
+    let dirs = dirs(&root);
+    let mut env = env_with_profile(&dirs).await;
+    let (jar, index) = (b"client", br#"{ "objects": {} }"#);
+    let download = |data: &[u8]| {
+        let digest = FileDigest::of_bytes(data);
+        json!({ "sha1": digest.sha1, "size": digest.size, "url": "" })
+    };
+    let client = json!({
+        "arguments": { "game": [], "jvm": [] },
+        "assetIndex": download(index),
+        "assets": "5",
+        "downloads": { "client": download(jar) },
+        "libraries": [],
+        "mainClass": "net.minecraft.client.main.Main",
+    });
+    write(&root.join("profiles/game/client.json"), client.to_string());
+    fs::write(root.join("profiles/game/client.jar"), jar).unwrap();
+    fs::create_dir_all(root.join("assets/indexes")).unwrap();
+    fs::write(root.join("assets/indexes/5.json"), index).unwrap();
+
+    let previous = write(
+        &root.join("natives/game/1.19.4-0123456789ab/liblwjgl.so"),
+        "",
+    );
+    // stray files, such as the metadata some file managers leave behind, used to fail the install
+    let stray = write(&root.join("natives/game/.DS_Store"), "");
+
+    env.install("game", |_| false).await.unwrap();
+    assert!(root
+        .join("natives/game/1.20.1-da39a3ee5e6b/.extracted")
+        .exists());
+    assert!(!previous.parent().unwrap().exists());
+    assert!(!stray.exists());
+}
+
+#[tokio::test]
+async fn rejects_names_escaping_the_profiles_directory() {
+    let root = TempDir::new("names");
+    let dirs = dirs(&root);
+    let mut env = Env::new(&dirs, true).unwrap();
+
+    for name in ["", ".", "..", "a/b", "a\\b", "../game", "/game"] {
+        let err = env.add(name, "1.20.1").await.unwrap_err();
+        assert!(matches!(err, CoreError::InvalidProfileName(n) if n == name));
+    }
+    assert!(env.add("game 1.20", "1.20.1").await.is_ok());
+    assert_eq!(env.profiles().fetch_profiles().len(), 1);
+}
+
+/// `del .. --purge` used to delete the whole launcher directory
+#[tokio::test]
+async fn purge_is_refused_outside_the_profiles_directory() {
+    let root = TempDir::new("purge-escape");
+    let dirs = dirs(&root);
+    // written by a launcher that didn't check profile names yet
+    let profiles = json!([{ "name": "..", "version": "1.20.1" }]);
+    fs::write(root.join("profiles.json"), profiles.to_string()).unwrap();
+    let world = write(&root.join("profiles/game/saves/world/level.dat"), "");
+    let mut env = Env::new(&dirs, true).unwrap();
+
+    let err = env.remove("..", true).unwrap_err();
+    assert!(matches!(err, CoreError::InvalidProfileName("..")));
+    assert!(world.exists());
+    assert!(root.join("profiles.json").exists());
+}
+
+#[tokio::test]
+async fn refuses_to_launch_a_running_profile() {
+    let root = TempDir::new("launch-running");
+    let dirs = dirs(&root);
+    let env = env_with_profile(&dirs).await;
+    fs::create_dir_all(root.join("profiles/game")).unwrap();
+
+    let lock = File::create(root.join("profiles/game/.lock")).unwrap();
+    lock.try_lock().unwrap();
+    let err = env.launch("game", &[]).unwrap_err();
+    assert!(matches!(err, CoreError::ProfileRunning("game")));
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use std::{collections::HashSet, path::Path};
+
+use common::{write, TempDir};
+use crab_launcher_core::gc;
+
+#[test]
+fn collects_unreferenced_files() {
+    let root = TempDir::new("collect");
+    let (assets, libs) = (root.join("assets"), root.join("libs"));
+    let used = [
+        write(&libs.join("org/used/1/used.jar"), b"used"),
+        write(&assets.join("indexes/1.json"), b"{}"),
+        write(&assets.join("objects/aa/aabb"), b"object"),
+    ];
+    let unused = [
+        write(&libs.join("org/unused/1/unused.jar"), b"unused"),
+        write(&assets.join("indexes/2.json"), b"{}"),
+        write(&assets.join("objects/cc/ccdd"), b"old"),
+    ];
+
+    let referenced: HashSet<_> = used.iter().cloned().collect();
+    let garbage = gc::collect(&assets, &libs, &[], &referenced).unwrap();
+
+    let mut files: Vec<_> = garbage.files().map(Path::to_path_buf).collect();
+    files.sort();
+    let mut expected = unused.to_vec();
+    expected.sort();
+    assert_eq!(files, expected);
+    assert_eq!(garbage.bytes(), 11);
+    // nothing is deleted until asked to
+    assert!(unused.iter().all(|file| file.exists()));
+}
+
+#[test]
+fn removes_emptied_directories() {
+    let root = TempDir::new("remove");
+    let (assets, libs) = (root.join("assets"), root.join("libs"));
+    let used = write(&libs.join("org/x/used/1/used.jar"), b"used");
+    write(&libs.join("org/x/unused/1/unused.jar"), b"unused");
+    write(&assets.join("objects/cc/ccdd"), b"old");
+    let natives = root.join("profiles/deleted/.natives");
+    write(&natives.join("liblwjgl.so"), b"native");
+    write(&root.join("profiles/deleted/saves/world"), b"world");
+
+    let referenced = HashSet::from([used.clone()]);
+    let garbage = gc::collect(&assets, &libs, std::slice::from_ref(&natives), &referenced).unwrap();
+    assert_eq!(garbage.len(), 3);
+    garbage.remove().unwrap();
+
+    assert!(used.exists());
+    assert!(!libs.join("org/x/unused").exists());
+    assert!(assets.join("objects").exists());
+    assert!(!assets.join("objects/cc").exists());
+    assert!(!natives.exists());
+    assert!(root.join("profiles/deleted/saves/world").exists());
+    assert!(gc::collect(&assets, &libs, &[natives], &referenced)
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,20 @@
+use crab_launcher_core::java::JavaInstallation;
+
+fn major(version: &str) -> u64 {
+    JavaInstallation::new("java".to_string(), version)
+        .expect("failed to parse java version")
+        .major()
+}
+
+#[test]
+fn releases_up_to_java_8_are_numbered_1_x() {
+    assert_eq!(major("1.8.0_392"), 8);
+    assert_eq!(major("1.7.0_80"), 7);
+}
+
+#[test]
+fn later_releases_are_numbered_by_their_major() {
+    assert_eq!(major("17.0.9"), 17);
+    assert_eq!(major("21.0.1"), 21);
+    assert!(major("17.0.9") > major("1.8.0_392"));
+}
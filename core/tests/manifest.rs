@@ -0,0 +1,93 @@
+mod common;
+
+use std::{
+    fs,
+    io::Write,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use common::{header, respond, write_head, StandIn, TempDir};
+use crab_launcher_core::{dirs::LauncherDirs, env::Env, scheduler::Progress};
+use futures::StreamExt;
+use serde_json::json;
+
+const MANIFEST: &str = r#"{
+    "latest": { "release": "1.20.1", "snapshot": "1.20.1" },
+    "versions": [{
+        "id": "1.20.1",
+        "type": "release",
+        "url": "https://piston-meta.mojang.com/v1/packages/b0/1.20.1.json",
+        "sha1": "b0cbd5d7a1e2d4e4b3e0ef7bb8d3e5d6d0c3b1f4",
+        "time": "2023-06-12T13:25:51+00:00",
+        "releaseTime": "2023-06-12T13:25:51+00:00"
+    }]
+}"#;
+const ETAG: &str = "\"v1\"";
+
+/// a launcher whose downloads from Mojang's servers go to `server`, with `manifest` cached as the version manifest
+/// the cache was checked `age` seconds ago against the manifest with the validator [`ETAG`]
+fn launcher_root(server: &StandIn, root: &TempDir, manifest: &str, age: u64) -> LauncherDirs {
+    let config = json!({ "mirrors": { "https://piston-meta.mojang.com/": [server.url("/")] } });
+    fs::write(root.join("config.json"), config.to_string()).unwrap();
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let meta = json!({ "etag": ETAG, "checked_at": now - age });
+    fs::write(root.join("version_manifest_v2.json"), manifest).unwrap();
+    fs::write(root.join("version_manifest_v2.meta.json"), meta.to_string()).unwrap();
+    LauncherDirs::rooted(root)
+}
+
+/// the downloader is configured once per process, so every case runs against the same server
+#[tokio::test]
+async fn corrupted_cache_is_downloaded_again() {
+    static DOWN: AtomicBool = AtomicBool::new(false);
+    let server = StandIn::start(|request, stream| {
+        if DOWN.load(Ordering::SeqCst) {
+            respond(stream, "404 Not Found", &[]);
+        } else if request.header("if-none-match").as_deref() == Some(ETAG) {
+            respond(stream, "304 Not Modified", &[]);
+        } else {
+            write_head(stream, "200 OK", MANIFEST.len(), &[format!("ETag: {ETAG}")]);
+            let _ = stream.write_all(MANIFEST.as_bytes());
+        }
+    });
+
+    // a fresh cache is trusted without checking the server, unless it doesn't parse
+    let root = TempDir::new("fresh");
+    let dirs = launcher_root(&server, &root, "{ truncated", 0);
+    let env = Env::new(&dirs, false).unwrap();
+    assert_eq!(env.versions().await.unwrap().count(), 1);
+    assert!(!env.is_offline());
+
+    // an expired cache would be answered with 304 if its validators were sent
+    let root = TempDir::new("expired");
+    let dirs = launcher_root(&server, &root, "{ truncated", 3600);
+    let env = Env::new(&dirs, false).unwrap();
+    assert_eq!(env.versions().await.unwrap().count(), 1);
+    assert_eq!(
+        fs::read_to_string(root.join("version_manifest_v2.json")).unwrap(),
+        MANIFEST
+    );
+
+    let heads = server.heads();
+    assert_eq!(heads.len(), 2);
+    assert!(heads
+        .iter()
+        .all(|head| header(head, "if-none-match").is_none()));
+
+    // a manifest that can't be downloaded falls back to the cache, which is reported to the subscriber
+    DOWN.store(true, Ordering::SeqCst);
+    let root = TempDir::new("offline");
+    let dirs = launcher_root(&server, &root, MANIFEST, 3600);
+    let env = Env::new(&dirs, false).unwrap();
+    let events = env.scheduler().subscribe();
+    assert_eq!(env.versions().await.unwrap().count(), 1);
+    assert!(env.is_offline());
+    env.scheduler().unsubscribe();
+    let events: Vec<_> = events.collect().await;
+    assert!(matches!(&events[..], [Progress::Offline { .. }]));
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use std::fs::{self, File};
+
+use common::TempDir;
+use crab_launcher_core::{
+    profiles::{self, Profile, ProfileMetadata},
+    runtimes::Runtimes,
+};
+
+#[test]
+fn running_while_the_lock_is_held() {
+    let root = TempDir::new("running");
+    let runtimes = Runtimes::new(&root.join("runtimes"), "");
+    let metadata = ProfileMetadata::new("game".to_string(), "1.20.1".to_string());
+    let profile = Profile::new(metadata, &root, &root, &root, &root, &root, &runtimes);
+    let lock_path = profile.dir_path().join(".lock");
+
+    assert!(!profile.is_running());
+
+    fs::create_dir_all(profile.dir_path()).unwrap();
+    let lock = File::create(&lock_path).unwrap();
+    lock.try_lock().unwrap();
+    assert!(profile.is_running());
+
+    // a lock file left behind by a launcher that didn't exit cleanly isn't held by anyone
+    drop(lock);
+    assert!(!profile.is_running());
+    fs::write(&lock_path, "12345").unwrap();
+    assert!(!profile.is_running());
+}
+
+#[test]
+fn only_one_instance_holds_the_lock() {
+    let root = TempDir::new("lock");
+    let runtimes = Runtimes::new(&root.join("runtimes"), "");
+    let metadata = ProfileMetadata::new("game".to_string(), "1.20.1".to_string());
+    let profile = Profile::new(metadata, &root, &root, &root, &root, &root, &runtimes);
+
+    let lock = profile.lock().unwrap().expect("the profile isn't running");
+    assert!(profile.is_running());
+    assert!(profile.lock().unwrap().is_none());
+
+    drop(lock);
+    assert!(!profile.dir_path().join(".lock").exists());
+    assert!(profile.lock().unwrap().is_some());
+}
+
+#[test]
+fn names_are_single_path_components() {
+    for name in ["", ".", "..", "a/b", "a\\b", "../game", "/game"] {
+        assert!(!profiles::is_valid_name(name), "{name:?}");
+    }
+    assert!(profiles::is_valid_name("game 1.20"));
+}
@@ -0,0 +1,188 @@
+mod common;
+
+use std::{
+    collections::HashMap,
+    fs,
+    os::unix::fs::PermissionsExt,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use common::{respond, StandIn, TempDir};
+
+use crab_launcher_api::meta::{runtime, utils::Platform};
+use crab_launcher_core::{
+    runtimes::Runtimes, scheduler::Scheduler, utils::download::FileDigest, utils::errors::CoreError,
+};
+use serde_json::{json, Value};
+
+const COMPONENT: &str = "java-runtime-gamma";
+const JAVA: &[u8] = b"#!/bin/sh\necho java\n";
+const LIBJLI: &[u8] = b"libjli";
+
+/// A stand-in server serving files by path
+struct Fixture {
+    server: StandIn,
+    /// the files served, by path, they can be added once the server is started
+    files: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+}
+
+impl Fixture {
+    fn start() -> Self {
+        let files = Arc::new(Mutex::new(HashMap::<String, Vec<u8>>::new()));
+        let served = files.clone();
+        let server = StandIn::start(move |request, stream| {
+            match served.lock().unwrap().get(request.path()) {
+                Some(body) => respond(stream, "200 OK", body),
+                None => respond(stream, "404 Not Found", &[]),
+            }
+        });
+        Self { server, files }
+    }
+
+    fn add(&self, path: &str, data: &[u8]) {
+        self.files
+            .lock()
+            .unwrap()
+            .insert(path.to_string(), data.to_vec());
+    }
+}
+
+/// the download entry of `data` served at `path`
+fn download(server: &StandIn, path: &str, data: &[u8]) -> Value {
+    json!({
+        "sha1": FileDigest::of_bytes(data).sha1,
+        "size": data.len(),
+        "url": server.url(path),
+    })
+}
+
+/// the name of the java executable inside a runtime, it depends on the platform
+fn java_name() -> String {
+    let runtimes = Runtimes::new(Path::new(""), "");
+    let java = runtimes.java_path(COMPONENT);
+    java.strip_prefix(runtimes.dir(COMPONENT))
+        .unwrap()
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// serves a runtime index with `COMPONENT` for the current platform, laid out like Mojang's runtimes
+fn runtime_fixture() -> Fixture {
+    let fixture = Fixture::start();
+    let server = &fixture.server;
+
+    let manifest = json!({
+        "files": {
+            "bin": { "type": "directory" },
+            java_name(): {
+                "type": "file",
+                "executable": true,
+                "downloads": { "raw": download(server, "/files/java", JAVA) },
+            },
+            "lib/libjli.so": {
+                "type": "file",
+                "executable": false,
+                "downloads": { "raw": download(server, "/files/libjli", LIBJLI) },
+            },
+            "lib/jli/libjli.so": { "type": "link", "target": "../libjli.so" },
+            "lib/escape": { "type": "link", "target": "../../outside" },
+            "../outside": { "type": "directory" },
+        }
+    })
+    .to_string();
+    let index = json!({
+        runtime::platform_key(Platform::CURRENT): {
+            COMPONENT: [{
+                "manifest": download(server, "/manifest.json", manifest.as_bytes()),
+                "version": { "name": "17.0.8", "released": "2023-07-24T00:00:00+00:00" },
+            }]
+        }
+    })
+    .to_string();
+
+    fixture.add("/all.json", index.as_bytes());
+    fixture.add("/manifest.json", manifest.as_bytes());
+    fixture.add("/files/java", JAVA);
+    fixture.add("/files/libjli", LIBJLI);
+    fixture
+}
+
+fn runtimes(fixture: &Fixture, root: &Path) -> Runtimes {
+    Runtimes::new(root, &fixture.server.url("/all.json"))
+}
+
+#[tokio::test]
+async fn installs_runtime() {
+    let fixture = runtime_fixture();
+    let root = TempDir::new("install");
+    let runtimes = runtimes(&fixture, &root);
+
+    let java = runtimes
+        .install(COMPONENT, &Scheduler::default())
+        .await
+        .unwrap();
+    assert_eq!(java, runtimes.java_path(COMPONENT));
+    assert_eq!(fs::read(&java).unwrap(), JAVA);
+    assert_ne!(fs::metadata(&java).unwrap().permissions().mode() & 0o111, 0);
+
+    let dir = runtimes.dir(COMPONENT);
+    let link = dir.join("lib/jli/libjli.so");
+    assert_eq!(fs::read_link(&link).unwrap(), Path::new("../libjli.so"));
+    assert_eq!(fs::read(&link).unwrap(), LIBJLI);
+    // entries leaving the runtime's directory are skipped
+    assert!(fs::symlink_metadata(dir.join("lib/escape")).is_err());
+    assert!(!root.join("outside").exists());
+}
+
+#[tokio::test]
+async fn skips_installed_runtime() {
+    let fixture = runtime_fixture();
+    let root = TempDir::new("installed");
+    let runtimes = runtimes(&fixture, &root);
+    let scheduler = Scheduler::default();
+
+    runtimes.install(COMPONENT, &scheduler).await.unwrap();
+    let java = runtimes.install(COMPONENT, &scheduler).await.unwrap();
+    assert!(java.exists());
+
+    // the second install only checks the index
+    let requests = fixture.server.paths();
+    assert_eq!(
+        requests.iter().filter(|path| *path == "/all.json").count(),
+        2
+    );
+    assert_eq!(
+        requests
+            .iter()
+            .filter(|path| *path == "/manifest.json")
+            .count(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn rejects_corrupted_manifest() {
+    let fixture = runtime_fixture();
+    fixture.add("/manifest.json", b"{\"files\": {}}");
+    let root = TempDir::new("corrupted");
+
+    let result = runtimes(&fixture, &root)
+        .install(COMPONENT, &Scheduler::default())
+        .await;
+    assert!(matches!(result, Err(CoreError::DownloadError(_))));
+    assert!(!root.join(COMPONENT).exists());
+}
+
+#[tokio::test]
+async fn unknown_component() {
+    let fixture = runtime_fixture();
+    let root = TempDir::new("unknown");
+
+    let result = runtimes(&fixture, &root)
+        .install("jre-legacy-unknown", &Scheduler::default())
+        .await;
+    assert!(
+        matches!(result, Err(CoreError::JavaRuntimeNotFound(component)) if component == "jre-legacy-unknown")
+    );
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use std::fs;
+
+use common::{respond, StandIn, TempDir};
+use crab_launcher_core::{
+    client::RequiredFile,
+    scheduler::{Progress, Scheduler},
+    utils::download::FileDigest,
+};
+use futures::StreamExt;
+
+fn required_files(server: &StandIn, dir: &TempDir) -> Vec<RequiredFile> {
+    (0..4)
+        .map(|i| {
+            let data = format!("file {i}");
+            RequiredFile {
+                path: dir.join(format!("objects/{i}")),
+                url: server.url(&format!("/{i}")),
+                expected: FileDigest::of_bytes(data.as_bytes()),
+            }
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn downloads_then_only_verifies() {
+    let server = StandIn::start(|request, stream| {
+        let i = request.path().trim_start_matches('/');
+        respond(stream, "200 OK", format!("file {i}").as_bytes())
+    });
+    let dir = TempDir::new("scheduler");
+    // a single slot still lets every file be verified and downloaded
+    let scheduler = Scheduler::new(1, 1);
+
+    scheduler.run(required_files(&server, &dir)).await.unwrap();
+    assert_eq!(server.heads().len(), 4);
+    assert_eq!(fs::read(dir.join("objects/3")).unwrap(), b"file 3");
+
+    let events = scheduler.subscribe();
+    scheduler.run(required_files(&server, &dir)).await.unwrap();
+    scheduler.unsubscribe();
+    let events: Vec<_> = events.collect().await;
+    assert_eq!(server.heads().len(), 4);
+    assert_eq!(
+        events
+            .iter()
+            .filter(|event| matches!(event, Progress::Verified { .. }))
+            .count(),
+        4
+    );
+    assert!(!events
+        .iter()
+        .any(|event| matches!(event, Progress::Started { .. })));
+}
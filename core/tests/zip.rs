@@ -0,0 +1,67 @@
+mod common;
+
+use std::{
+    fs,
+    io::{Cursor, Write},
+    path::Path,
+};
+
+use common::TempDir;
+use crab_launcher_core::utils::zip::{Glob, ZipExtractor};
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+/// an archive laid out like the natives jars of LWJGL 3
+fn natives_jar() -> Cursor<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    for name in [
+        "META-INF/MANIFEST.MF",
+        "META-INF/versions/9/module-info.class",
+        "linux/x64/org/lwjgl/liblwjgl.so",
+        "linux/x64/org/lwjgl/liblwjgl.so.sha1",
+        "liblwjgl.so",
+    ] {
+        zip.start_file(name, SimpleFileOptions::default()).unwrap();
+        zip.write_all(name.as_bytes()).unwrap();
+    }
+    let mut reader = zip.finish().unwrap();
+    reader.set_position(0);
+    reader
+}
+
+#[test]
+fn directory_patterns_match_everything_inside() {
+    let glob = Glob::new("META-INF/");
+    assert!(glob.matches(Path::new("META-INF")));
+    assert!(glob.matches(Path::new("META-INF/MANIFEST.MF")));
+    assert!(glob.matches(Path::new("META-INF/versions/9/module-info.class")));
+    assert!(!glob.matches(Path::new("linux/META-INF/MANIFEST.MF")));
+    assert!(!glob.matches(Path::new("META-INF.txt")));
+}
+
+#[test]
+fn wildcards() {
+    assert!(Glob::new("**/META-INF/").matches(Path::new("linux/x64/META-INF/MANIFEST.MF")));
+    assert!(Glob::new("**/META-INF/").matches(Path::new("META-INF/MANIFEST.MF")));
+    assert!(Glob::new("*.sha1").matches(Path::new("liblwjgl.so.sha1")));
+    assert!(!Glob::new("*.sha1").matches(Path::new("linux/liblwjgl.so.sha1")));
+    assert!(Glob::new("**/*.sha1").matches(Path::new("linux/x64/liblwjgl.so.sha1")));
+    assert!(Glob::new("lib?wjgl.so").matches(Path::new("liblwjgl.so")));
+    assert!(!Glob::new("lib?wjgl.so").matches(Path::new("libwjgl.so")));
+}
+
+#[test]
+fn extracts_without_excluded_entries() {
+    let output = TempDir::new("extract");
+    ZipExtractor::new(natives_jar())
+        .exclude([Glob::new("META-INF/"), Glob::new("**/*.sha1")])
+        .extract(&output)
+        .unwrap();
+
+    assert!(!output.join("META-INF").exists());
+    assert!(output.join("linux/x64/org/lwjgl/liblwjgl.so").exists());
+    assert!(!output.join("linux/x64/org/lwjgl/liblwjgl.so.sha1").exists());
+    assert_eq!(
+        fs::read_to_string(output.join("liblwjgl.so")).unwrap(),
+        "liblwjgl.so"
+    );
+}
@@ -1,8 +1,20 @@
-use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use crab_launcher_api::meta::manifest::VersionKind;
 
 #[derive(Debug, Parser)]
 #[command(author, version, about)]
 pub struct Cli {
+    /// keeps all of the launcher's files in the given directory instead of the XDG base directories [env: CRAB_LAUNCHER_ROOT]
+    #[arg(long, global = true)]
+    pub root: Option<PathBuf>,
+    /// keeps all of the launcher's files in a `launcher` directory inside the current directory
+    #[arg(long, global = true)]
+    pub portable: bool,
+    /// never touches the network, profiles can only be launched if all of their files are already downloaded
+    #[arg(long, global = true)]
+    pub offline: bool,
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -13,20 +25,138 @@ pub struct New {
     pub version: String,
 }
 
+#[derive(Debug, Clone, Copy, ValueEnum)]
+#[value(rename_all = "snake_case")]
+pub enum Kind {
+    Release,
+    Snapshot,
+    OldAlpha,
+    OldBeta,
+}
+
+impl From<Kind> for VersionKind {
+    fn from(kind: Kind) -> Self {
+        match kind {
+            Kind::Release => VersionKind::Release,
+            Kind::Snapshot => VersionKind::Snapshot,
+            Kind::OldAlpha => VersionKind::OldAlpha,
+            Kind::OldBeta => VersionKind::OldBeta,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Sort {
+    Newest,
+    Oldest,
+}
+
+#[derive(Args, Debug)]
+pub struct Versions {
+    /// only lists versions of the given kinds, can be repeated
+    #[arg(long)]
+    pub kind: Vec<Kind>,
+    /// orders versions by their release date
+    #[arg(long, value_enum, default_value_t = Sort::Newest)]
+    pub sort: Sort,
+    /// only lists versions released on or after the given date (YYYY, YYYY-MM or YYYY-MM-DD)
+    #[arg(long, value_parser = parse_date)]
+    pub since: Option<String>,
+}
+
+/// validates a date in the form YYYY, YYYY-MM or YYYY-MM-DD
+fn parse_date(date: &str) -> Result<String, String> {
+    let parts: Vec<&str> = date.split('-').collect();
+    let valid = !parts.is_empty()
+        && parts.len() <= 3
+        && parts.iter().enumerate().all(|(i, part)| {
+            let len = if i == 0 { 4 } else { 2 };
+            part.len() == len && part.chars().all(|c| c.is_ascii_digit())
+        });
+
+    if valid {
+        Ok(date.to_string())
+    } else {
+        Err(format!(
+            "invalid date `{date}`, expected YYYY, YYYY-MM or YYYY-MM-DD"
+        ))
+    }
+}
+
 #[derive(Debug, Subcommand)]
 pub enum Commands {
     New(New),
     Edit {
         name: String,
         entry: String,
+        #[arg(allow_hyphen_values = true)]
         value: Option<String>,
     },
 
     Run {
         name: String,
+        /// overrides a config entry for this launch only, can be repeated
+        #[arg(long = "set", value_name = "ENTRY=VALUE", value_parser = parse_override)]
+        overrides: Vec<(String, String)>,
+        /// joins the given server once the game starts
+        #[arg(long, value_name = "HOST[:PORT]", group = "quick_play")]
+        server: Option<String>,
+        /// opens the given singleplayer world once the game starts
+        #[arg(long, group = "quick_play")]
+        world: Option<String>,
+        /// joins the realm with the given id once the game starts
+        #[arg(long, group = "quick_play")]
+        realm: Option<String>,
     },
     Del {
         name: String,
+        /// also deletes the profile's directory, including its saves
+        #[arg(long)]
+        purge: bool,
+        /// backs up the profile's saves before purging without asking
+        #[arg(long, requires = "purge", conflicts_with = "no_backup")]
+        backup: bool,
+        /// purges the profile without backing up its saves
+        #[arg(long, requires = "purge")]
+        no_backup: bool,
     },
     List,
+    Versions(Versions),
+    /// reports the files of a profile that are missing or corrupted
+    Verify {
+        name: String,
+        /// downloads the missing and corrupted files again
+        #[arg(long)]
+        repair: bool,
+    },
+    /// deletes the libraries, assets and natives that no profile uses anymore
+    Gc {
+        /// only reports what would be deleted
+        #[arg(long)]
+        dry_run: bool,
+    },
+    #[command(subcommand)]
+    Config(ConfigCommand),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// prints the effective config of a profile
+    Show {
+        name: String,
+        /// also prints the layer each value comes from
+        #[arg(long)]
+        origin: bool,
+        /// overrides a config entry, like `run --set`
+        #[arg(long = "set", value_name = "ENTRY=VALUE", value_parser = parse_override)]
+        overrides: Vec<(String, String)>,
+    },
+}
+
+/// parses an `entry=value` pair
+fn parse_override(pair: &str) -> Result<(String, String), String> {
+    let (entry, value) = pair
+        .split_once('=')
+        .ok_or_else(|| format!("invalid override `{pair}`, expected ENTRY=VALUE"))?;
+    Ok((entry.to_string(), value.to_string()))
 }
@@ -1,53 +1,304 @@
 mod cli;
-use std::path::Path;
+mod progress;
+use std::io::{self, Write};
 
 use clap::Parser;
-use cli::Cli;
-use crab_launcher_core::{env::Env, utils::errors::CoreError};
+use cli::{Cli, ConfigCommand, Sort};
+use crab_launcher_api::meta::manifest::VersionKind;
+use crab_launcher_core::{
+    client::FileStatus,
+    dirs::LauncherDirs,
+    env::Env,
+    utils::{download::DownloadError, errors::CoreError},
+};
+use progress::ProgressBar;
 #[tokio::main]
 async fn main() {
     let parse = Cli::try_parse().unwrap_or_else(|e| e.exit());
-    let launcher_root = Path::new("launcher");
-    let mut env = Env::fetch_new(launcher_root).await;
+    // the flags take precedence over `CRAB_LAUNCHER_ROOT`
+    let dirs = match (parse.portable, &parse.root) {
+        (true, Some(_)) => {
+            return eprintln!("--portable and --root can't be used together");
+        }
+        (true, None) => LauncherDirs::portable(),
+        (false, Some(root)) => LauncherDirs::rooted(root),
+        (false, None) => match LauncherDirs::from_env() {
+            Ok(dirs) => dirs,
+            Err(err) => return report_error(err),
+        },
+    };
+    let mut env = match Env::new(&dirs, parse.offline) {
+        Ok(env) => env,
+        Err(err) => return report_error(err),
+    };
 
     match parse.command {
-        cli::Commands::New(new) => env
-            .add(&new.name, &new.version)
-            .await
-            .expect("failed to add profile"),
-
-        cli::Commands::Edit { name, entry, value } => env
-            .edit(&name, &entry, value.clone())
-            .expect(&format!("failed to set {name}'s {entry} to {value:?}")),
-
-        cli::Commands::Run { name } => match env.execute(&name).await {
-            Ok(_) => println!("Minecraft exited successfully"),
-            Err(err) => match err {
-                CoreError::MinecraftFailure(exit_code) => {
-                    eprintln!("Minecraft exited with code {}", exit_code);
+        cli::Commands::New(new) => {
+            if let Err(err) = env.add(&new.name, &new.version).await {
+                report_error(err);
+            }
+        }
+
+        cli::Commands::Edit { name, entry, value } => {
+            if let Err(err) = env.edit(&name, &entry, value) {
+                report_error(err);
+            }
+        }
+
+        cli::Commands::Run {
+            name,
+            mut overrides,
+            server,
+            world,
+            realm,
+        } => {
+            let quick_play = [
+                ("quick_play_multiplayer", server),
+                ("quick_play_singleplayer", world),
+                ("quick_play_realms", realm),
+            ];
+            for (entry, value) in quick_play {
+                if let Some(value) = value {
+                    overrides.push((entry.to_string(), value));
+                }
+            }
+
+            let progress = ProgressBar::start(env.scheduler());
+            let installed = env
+                .install(&name, |version| {
+                    confirm(&format!(
+                        "Mojang republished {version} since `{name}` was installed, refresh it?"
+                    ))
+                })
+                .await;
+            progress.finish(env.scheduler()).await;
+
+            match installed.and_then(|_| env.launch(&name, &overrides)) {
+                Ok(_) => println!("Minecraft exited successfully"),
+                Err(err) => report_error(err),
+            }
+        }
+        cli::Commands::Del {
+            name,
+            purge,
+            backup,
+            no_backup,
+        } => {
+            // the saves of a running game are still being written to, so nothing is backed up or deleted
+            match env.is_running(&name) {
+                Ok(false) => {}
+                Ok(true) => return report_error(CoreError::ProfileRunning(&name)),
+                Err(err) => return report_error(err),
+            }
+            if purge && !no_backup && (backup || confirm("Back up saves before purging?")) {
+                match env.backup_saves(&name) {
+                    Ok(Some(path)) => println!("Backed up saves to {}", path.display()),
+                    Ok(None) => {}
+                    Err(err) => return report_error(err),
                 }
-                CoreError::IoError(err) => {
-                    eprintln!("IO error: {}", err);
+            }
+
+            match env.remove(&name, purge) {
+                Ok(_) => println!("Deleted profile `{}`", name),
+                Err(err) => report_error(err),
+            }
+        }
+        cli::Commands::Versions(versions) => {
+            let kinds: Vec<VersionKind> = versions.kind.into_iter().map(Into::into).collect();
+            let progress = ProgressBar::start(env.scheduler());
+            let versions_list = env.versions().await;
+            progress.finish(env.scheduler()).await;
+            let versions_list = match versions_list {
+                Ok(list) => list,
+                Err(err) => return report_error(err),
+            };
+            let mut list: Vec<_> = versions_list
+                .filter(|v| kinds.is_empty() || kinds.contains(&v.kind))
+                .filter(|v| {
+                    versions
+                        .since
+                        .as_ref()
+                        .is_none_or(|since| v.release_time.as_str() >= since.as_str())
+                })
+                .collect();
+
+            list.sort_by(|a, b| match versions.sort {
+                Sort::Newest => b.release_time.cmp(&a.release_time),
+                Sort::Oldest => a.release_time.cmp(&b.release_time),
+            });
+
+            for version in list {
+                let date = version
+                    .release_time
+                    .get(..10)
+                    .unwrap_or(&version.release_time);
+                println!("{}\t{}\t{}", version.id, version.kind, date);
+            }
+        }
+        cli::Commands::Config(ConfigCommand::Show {
+            name,
+            origin,
+            overrides,
+        }) => match env.config_layers(&name, &overrides) {
+            Ok(layers) => {
+                for (entry, value, layer) in layers.entries() {
+                    if origin {
+                        println!("{entry} = {value}\t({layer})");
+                    } else {
+                        println!("{entry} = {value}");
+                    }
                 }
+            }
+            Err(err) => report_error(err),
+        },
+        cli::Commands::Verify { name, repair } => {
+            let broken = match env.verify(&name) {
+                Ok(broken) => broken,
+                Err(err) => return report_error(err),
+            };
+
+            for (file, status) in &broken {
+                let status = match status {
+                    FileStatus::Missing => "missing",
+                    FileStatus::Corrupted => "corrupted",
+                    FileStatus::Valid => unreachable!(),
+                };
+                println!("{}:\t{}", status, file.path.display());
+            }
+
+            if broken.is_empty() {
+                println!("All files of `{}` are valid", name);
+            } else if repair {
+                let progress = ProgressBar::start(env.scheduler());
+                let repaired = env.repair(&name).await;
+                progress.finish(env.scheduler()).await;
 
-                CoreError::ZipError(err) => {
-                    eprintln!("Failed unzipping: {}", err);
+                match repaired {
+                    Ok(repaired) => println!("Repaired {} files", repaired.len()),
+                    Err(err) => report_error(err),
                 }
-                CoreError::ProfileNotFound(name) => {
-                    eprintln!("Profile `{}` not found", name);
+            } else {
+                println!(
+                    "{} files are missing or corrupted, run with --repair to download them again",
+                    broken.len()
+                );
+            }
+        }
+        cli::Commands::Gc { dry_run } => match env.gc(dry_run) {
+            Ok(garbage) => {
+                if dry_run {
+                    for file in garbage.files() {
+                        println!("{}", file.display());
+                    }
                 }
-                CoreError::DownloadError(err) => {
-                    eprintln!("Download error: {:?}", err);
+                let size = garbage.bytes() as f64 / (1024.0 * 1024.0);
+                if garbage.is_empty() {
+                    println!("Nothing to delete");
+                } else if dry_run {
+                    println!("{} files can be deleted, {:.1} MiB", garbage.len(), size);
+                } else {
+                    println!("Deleted {} files, {:.1} MiB", garbage.len(), size);
                 }
-                CoreError::MinecraftVersionNotFound => unreachable!(),
-            },
+            }
+            Err(err) => report_error(err),
         },
         cli::Commands::List => {
             println!("profiles:");
             for profile in env.profiles().fetch_profiles() {
-                println!("{}:\tversion: {}", profile.name(), profile.version());
+                if profile.is_alias() && profile.resolved_version() == profile.version() {
+                    println!(
+                        "{}:\tversion: {} (not yet resolved)",
+                        profile.name(),
+                        profile.version()
+                    );
+                } else if profile.is_alias() {
+                    println!(
+                        "{}:\tversion: {} ({})",
+                        profile.name(),
+                        profile.version(),
+                        profile.resolved_version()
+                    );
+                } else {
+                    println!("{}:\tversion: {}", profile.name(), profile.version());
+                }
+            }
+        }
+    }
+}
+
+/// asks the user a yes/no question on stdin, defaults to yes
+fn confirm(question: &str) -> bool {
+    print!("{question} [Y/n] ");
+    io::stdout().flush().expect("failed flushing stdout");
+
+    let mut answer = String::new();
+    io::stdin()
+        .read_line(&mut answer)
+        .expect("failed reading stdin");
+    !matches!(answer.trim().to_lowercase().as_str(), "n" | "no")
+}
+
+fn report_error(err: CoreError) {
+    match err {
+        CoreError::MinecraftFailure(exit_code) => {
+            eprintln!("Minecraft exited with code {}", exit_code);
+        }
+        CoreError::IoError(err) => {
+            eprintln!("IO error: {}", err);
+        }
+
+        CoreError::ZipError(err) => {
+            eprintln!("Failed unzipping: {}", err);
+        }
+        CoreError::ProfileNotFound(name) => {
+            eprintln!("Profile `{}` not found", name);
+        }
+        CoreError::ProfileNotInstalled(name) => {
+            eprintln!("Profile `{}` is not installed yet, run it first", name);
+        }
+        CoreError::Offline(missing) => {
+            eprintln!("The launcher is offline and these files have to be downloaded first:");
+            for path in missing {
+                eprintln!("\t{}", path.display());
             }
         }
-        _ => todo!(),
+        CoreError::ProfileRunning(name) => {
+            eprintln!("Profile `{}` is currently running", name);
+        }
+        CoreError::NoHomeDir => {
+            eprintln!(
+                "Failed to find the launcher directories, set HOME or pass --root or --portable"
+            );
+        }
+        CoreError::InvalidProfileName(name) => {
+            eprintln!(
+                "`{}` can't name a profile, profile names are used as directory names",
+                name
+            );
+        }
+        CoreError::DownloadError(DownloadError::Settings(reason)) => {
+            eprintln!("Invalid download settings, {}", reason);
+        }
+        CoreError::DownloadError(err) => {
+            eprintln!("Download error: {}", err);
+        }
+        CoreError::HookFailure(hook, exit_code) => {
+            eprintln!("`{}` hook exited with code {}", hook, exit_code);
+        }
+        CoreError::QuickPlayUnsupported(entry) => {
+            eprintln!("`{}` is not supported by this version", entry);
+        }
+        CoreError::ConfigError(err) => {
+            eprintln!("Invalid config: {}", err);
+        }
+        CoreError::JavaRuntimeNotFound(component) => {
+            eprintln!(
+                "Java runtime `{}` is not available for this platform",
+                component
+            );
+        }
+        CoreError::MinecraftVersionNotFound => {
+            eprintln!("Minecraft version not found");
+        }
     }
 }
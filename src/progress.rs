@@ -0,0 +1,141 @@
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use crab_launcher_core::scheduler::{Progress, Scheduler};
+use futures::{channel::mpsc::UnboundedReceiver, StreamExt};
+use tokio::task::JoinHandle;
+
+/// how often the progress bar is redrawn at most
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+const BAR_WIDTH: usize = 30;
+const MIB: f64 = 1024.0 * 1024.0;
+
+/// A progress bar drawn on stderr while the scheduler downloads files
+pub struct ProgressBar {
+    task: JoinHandle<()>,
+}
+
+impl ProgressBar {
+    /// starts drawing the progress of the downloads made by `scheduler`
+    pub fn start(scheduler: &Scheduler) -> Self {
+        let events = scheduler.subscribe();
+        Self {
+            task: tokio::spawn(draw_events(events)),
+        }
+    }
+
+    /// stops following `scheduler` and waits for the last events to be drawn
+    pub async fn finish(self, scheduler: &Scheduler) {
+        scheduler.unsubscribe();
+        let _ = self.task.await;
+    }
+}
+
+/// The files queued so far and how much of them is done
+#[derive(Default)]
+struct Totals {
+    files: usize,
+    done: usize,
+    failed: usize,
+    bytes: u64,
+    done_bytes: u64,
+    /// the expected size and the received bytes of each file that isn't done yet
+    pending: HashMap<PathBuf, (u64, u64)>,
+}
+
+impl Totals {
+    fn update(&mut self, event: Progress) {
+        match event {
+            Progress::Queued { path, size } => {
+                self.files += 1;
+                self.bytes += size;
+                self.pending.insert(path, (size, 0));
+            }
+            Progress::Started { .. } => {}
+            Progress::Bytes { path, bytes } => {
+                if let Some((size, received)) = self.pending.get_mut(&path) {
+                    // a restarted download sends the start of the file again
+                    let bytes = bytes.min(size.saturating_sub(*received));
+                    *received += bytes;
+                    self.done_bytes += bytes;
+                }
+            }
+            Progress::Verified { path } => {
+                self.done += 1;
+                if let Some((size, received)) = self.pending.remove(&path) {
+                    self.done_bytes += size - received;
+                }
+            }
+            Progress::Failed { path, error } => {
+                self.failed += 1;
+                self.pending.remove(&path);
+                // clears the bar before printing over it
+                eprintln!("\r\x1b[2KFailed to download {}: {}", path.display(), error);
+            }
+            Progress::InstallingRuntime { component, version } => {
+                eprintln!("\r\x1b[2KInstalling Java runtime {component} ({version})");
+            }
+            Progress::NoRuntime { component, major } => {
+                eprintln!("\r\x1b[2KNo Java {major} is installed and Mojang doesn't provide {component} for this platform, keeping the current Java");
+            }
+            Progress::AliasResolved {
+                profile,
+                alias,
+                version,
+            } => {
+                eprintln!("\r\x1b[2K{alias} now resolves to {version}, updating profile {profile}");
+            }
+            Progress::Offline { reason } => {
+                eprintln!("\r\x1b[2KFailed to download the version manifest ({reason}), continuing offline");
+            }
+        }
+    }
+
+    fn draw(&self) {
+        let ratio = if self.bytes == 0 {
+            1.0
+        } else {
+            self.done_bytes as f64 / self.bytes as f64
+        };
+        let filled = (ratio * BAR_WIDTH as f64) as usize;
+        let mut line = format!(
+            "\r[{}{}] {}/{} files, {:.1}/{:.1} MiB",
+            "#".repeat(filled),
+            "-".repeat(BAR_WIDTH - filled),
+            self.done,
+            self.files,
+            self.done_bytes as f64 / MIB,
+            self.bytes as f64 / MIB,
+        );
+        if self.failed > 0 {
+            line.push_str(&format!(", {} failed", self.failed));
+        }
+
+        let mut stderr = io::stderr();
+        let _ = stderr.write_all(line.as_bytes());
+        let _ = stderr.flush();
+    }
+}
+
+async fn draw_events(mut events: UnboundedReceiver<Progress>) {
+    let mut totals = Totals::default();
+    let mut last_draw: Option<Instant> = None;
+
+    while let Some(event) = events.next().await {
+        totals.update(event);
+        // nothing to draw until a file is queued, e.g. when only the manifest is loaded
+        if totals.files > 0 && last_draw.is_none_or(|last| last.elapsed() >= REDRAW_INTERVAL) {
+            totals.draw();
+            last_draw = Some(Instant::now());
+        }
+    }
+
+    if totals.files > 0 {
+        totals.draw();
+        eprintln!();
+    }
+}